@@ -0,0 +1,40 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use modeling::CtagsParser;
+
+/// tags of `classes` Java classes with 10 fields and 10 methods each
+fn java_tags(classes: usize) -> String {
+    let mut tags = String::new();
+    for i in 0..classes {
+        let file = format!("src/main/java/com/shop/Class{}.java", i);
+        tags.push_str(&format!(
+            "Class{}\t{}\t/^public class Class{} extends Base {{$/;\"\tclass\tline:3\tlanguage:Java\tinherits:Base\n",
+            i, file, i
+        ));
+        for j in 0..10 {
+            tags.push_str(&format!(
+                "field{}\t{}\t/^    private List<Order> field{};$/;\"\tfield\tline:{}\tlanguage:Java\tclass:Class{}\taccess:private\n",
+                j, file, j, 4 + j, i
+            ));
+            tags.push_str(&format!(
+                "method{}\t{}\t/^    public Order method{}(String name, Map<String, User> users) {{$/;\"\tmethod\tline:{}\tlanguage:Java\tclass:Class{}\taccess:public\n",
+                j, file, j, 20 + j, i
+            ));
+        }
+    }
+    tags
+}
+
+fn parse(c: &mut Criterion) {
+    let tags = java_tags(2000);
+
+    c.bench_function("parse 42k java tags", |b| {
+        b.iter(|| {
+            let parser = CtagsParser::parse_str(vec![black_box(&tags).lines()]);
+            black_box(parser.classes())
+        })
+    });
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
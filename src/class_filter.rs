@@ -0,0 +1,316 @@
+use std::fmt;
+
+use regex::Regex;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::ClassInfo;
+
+/// Filters parsed classes, a class is kept when it matches `includes` and none of `excludes`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ClassFilter {
+    pub includes: ClassRules,
+    pub excludes: ClassRules,
+}
+
+/// Criteria of classes, names, packages and annotations are regexes which match the whole text,
+/// languages and kinds compare ignoring case.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ClassRules {
+    #[serde(default)]
+    pub names: Vec<RulePattern>,
+    #[serde(default)]
+    pub packages: Vec<RulePattern>,
+    #[serde(default)]
+    pub languages: Vec<String>,
+    #[serde(default)]
+    pub kinds: Vec<String>,
+    #[serde(default)]
+    pub annotations: Vec<RulePattern>,
+}
+
+/// A regex of a rule compiled to match the whole text, it's compared and serialized by the
+/// pattern as given.
+#[derive(Clone)]
+pub struct RulePattern {
+    pattern: String,
+    regex: Regex,
+}
+
+impl RulePattern {
+    pub fn new(pattern: &str) -> Result<RulePattern, regex::Error> {
+        Ok(RulePattern {
+            pattern: pattern.to_string(),
+            regex: Regex::new(&format!("^(?:{})$", pattern))?,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+}
+
+impl fmt::Debug for RulePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.pattern)
+    }
+}
+
+impl PartialEq for RulePattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
+}
+
+impl Serialize for RulePattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.pattern)
+    }
+}
+
+impl<'de> Deserialize<'de> for RulePattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        RulePattern::new(&pattern).map_err(D::Error::custom)
+    }
+}
+
+impl ClassRules {
+    /// Adds a rule like `name:.*Service`, `package:com\.phodal\..*`, `lang:Java`, `kind:struct`
+    /// or `annotation:Entity`, a rule without prefix is a name. Regexes are compiled here, so an
+    /// invalid one is an error of the rule.
+    pub fn add(&mut self, rule: &str) -> Result<(), String> {
+        let (key, value) = match rule.split_once(':') {
+            Some((key, value)) if !value.starts_with(':') => (key, value),
+            _ => ("name", rule),
+        };
+
+        let pattern = || {
+            RulePattern::new(value).map_err(|err| format!("invalid class rule {}: {}", rule, err))
+        };
+        match key {
+            "name" => self.names.push(pattern()?),
+            "package" => self.packages.push(pattern()?),
+            "lang" | "language" => self.languages.push(value.to_string()),
+            "kind" => self.kinds.push(value.to_string()),
+            "annotation" => self.annotations.push(pattern()?),
+            _ => return Err(format!("unknown class rule: {}", rule)),
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+            && self.packages.is_empty()
+            && self.languages.is_empty()
+            && self.kinds.is_empty()
+            && self.annotations.is_empty()
+    }
+
+    /// every given criterion matches
+    fn match_all(&self, clazz: &ClassInfo) -> bool {
+        (self.names.is_empty() || self.match_name(clazz))
+            && (self.packages.is_empty() || self.match_package(clazz))
+            && (self.languages.is_empty() || self.match_language(clazz))
+            && (self.kinds.is_empty() || self.match_kind(clazz))
+            && (self.annotations.is_empty() || self.match_annotation(clazz))
+    }
+
+    /// any of the criteria matches
+    fn match_any(&self, clazz: &ClassInfo) -> bool {
+        self.match_name(clazz)
+            || self.match_package(clazz)
+            || self.match_language(clazz)
+            || self.match_kind(clazz)
+            || self.match_annotation(clazz)
+    }
+
+    fn match_name(&self, clazz: &ClassInfo) -> bool {
+        self.names.iter().any(|regex| regex.is_match(&clazz.name))
+    }
+
+    /// the package is declared by the source: the `package` statement of Java and Kotlin files,
+    /// namespaces of C# and C++ classes, or the module path of Rust types
+    fn match_package(&self, clazz: &ClassInfo) -> bool {
+        self.packages
+            .iter()
+            .any(|regex| regex.is_match(&clazz.package))
+    }
+
+    fn match_language(&self, clazz: &ClassInfo) -> bool {
+        self.languages
+            .iter()
+            .any(|lang| lang.eq_ignore_ascii_case(&clazz.lang))
+    }
+
+    fn match_kind(&self, clazz: &ClassInfo) -> bool {
+        self.kinds
+            .iter()
+            .any(|kind| kind.eq_ignore_ascii_case(&clazz.kind))
+    }
+
+    fn match_annotation(&self, clazz: &ClassInfo) -> bool {
+        self.annotations.iter().any(|regex| {
+            clazz
+                .annotations
+                .iter()
+                .any(|annotation| regex.is_match(annotation))
+        })
+    }
+}
+
+impl ClassFilter {
+    /// Builds the filter from rules of the command line, see `ClassRules::add`.
+    pub fn from_rules(includes: &[String], excludes: &[String]) -> Result<ClassFilter, String> {
+        let mut filter = ClassFilter::default();
+        for rule in includes {
+            filter.includes.add(rule)?;
+        }
+        for rule in excludes {
+            filter.excludes.add(rule)?;
+        }
+        Ok(filter)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.includes.is_empty() && self.excludes.is_empty()
+    }
+
+    pub fn filter(&self, classes: Vec<ClassInfo>) -> Vec<ClassInfo> {
+        if self.is_empty() {
+            return classes;
+        }
+
+        classes
+            .into_iter()
+            .filter(|clazz| self.includes.match_all(clazz) && !self.excludes.match_any(clazz))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use crate::class_filter::{ClassFilter, ClassRules};
+    use crate::ctags::rust_module::CrateRoots;
+    use crate::source::read_sources;
+    use crate::testing::class;
+    use crate::{ClassInfo, CtagsParser};
+
+    fn names(classes: &[ClassInfo]) -> Vec<&str> {
+        classes.iter().map(|clazz| clazz.name.as_str()).collect()
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    fn classes() -> Vec<ClassInfo> {
+        let mut order = class("Java", "com.shop.order", "Order").of_kind("class");
+        order.annotations.push("Entity".to_string());
+        vec![
+            order,
+            class("Java", "com.shop.order", "OrderService").of_kind("class"),
+            class("Java", "com.shop.order", "OrderServiceTest").of_kind("class"),
+            class("Java", "com.shop.user", "UserService").of_kind("class"),
+            class("Rust", "crate::geometry", "Point").of_kind("struct"),
+        ]
+    }
+
+    #[test]
+    fn should_keep_all_without_rules() {
+        assert_eq!(5, ClassFilter::default().filter(classes()).len());
+    }
+
+    #[test]
+    fn should_include_by_name_and_package() {
+        let filter =
+            ClassFilter::from_rules(&strings(&[".*Service", r"package:com\.shop\.order"]), &[])
+                .unwrap();
+
+        assert_eq!(vec!["OrderService"], names(&filter.filter(classes())));
+    }
+
+    #[test]
+    fn should_include_by_language_kind_and_annotation() {
+        let filter = ClassFilter::from_rules(&strings(&["lang:rust", "kind:struct"]), &[]).unwrap();
+        assert_eq!(vec!["Point"], names(&filter.filter(classes())));
+
+        let filter = ClassFilter::from_rules(&strings(&["annotation:Entity"]), &[]).unwrap();
+        assert_eq!(vec!["Order"], names(&filter.filter(classes())));
+    }
+
+    #[test]
+    fn should_exclude_any_matched_rule() {
+        let filter =
+            ClassFilter::from_rules(&[], &strings(&[".*Test", "package:com\\.shop\\.user"]))
+                .unwrap();
+
+        assert_eq!(
+            vec!["Order", "OrderService", "Point"],
+            names(&filter.filter(classes()))
+        );
+    }
+
+    #[test]
+    fn should_match_declared_package_of_parsed_classes() {
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path().join("Order.java"),
+            "package com.shop.order;\n\nclass Order {}",
+        )
+        .unwrap();
+        let tags = "Order\tOrder.java\t/^class Order {}$/;\"\tclass\tline:3\tlanguage:Java\n\
+                    Cart\tCart.cs\t/^  class Cart {$/;\"\tclass\tline:3\tlanguage:C#\tnamespace:Shop.Orders\n\
+                    Stock\tStock.cs\t/^  class Stock {$/;\"\tclass\tline:3\tlanguage:C#\tnamespace:Shop.Stock";
+        let mut parser = CtagsParser::default();
+        for line in tags.lines() {
+            parser.parse_line(line);
+        }
+        let mut classes = parser.classes();
+        read_sources(&mut classes, temp.path(), &CrateRoots::default());
+
+        let filter = ClassFilter::from_rules(
+            &strings(&[r"package:com\.shop\..*", r"package:Shop\.Orders"]),
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(vec!["Cart", "Order"], names(&filter.filter(classes)));
+    }
+
+    #[test]
+    fn should_reject_unknown_rule() {
+        assert!(ClassFilter::from_rules(&strings(&["size:1"]), &[]).is_err());
+    }
+
+    #[test]
+    fn should_reject_invalid_regex() {
+        let err = ClassFilter::from_rules(&[], &strings(&["package:com.(shop"])).unwrap_err();
+        assert!(err.starts_with("invalid class rule package:com.(shop"));
+    }
+
+    #[test]
+    fn should_serialize_rules_as_patterns() {
+        let filter = ClassFilter::from_rules(&strings(&[".*Service", "lang:Java"]), &[]).unwrap();
+
+        let json = serde_json::to_string(&filter.includes).unwrap();
+        assert!(json.contains(r#""names":[".*Service"]"#));
+        let rules: ClassRules = serde_json::from_str(&json).unwrap();
+        assert_eq!(filter.includes, rules);
+        assert!(serde_json::from_str::<ClassRules>(r#"{"names":["("]}"#).is_err());
+    }
+
+    #[test]
+    fn should_treat_rust_path_as_name() {
+        let filter = ClassFilter::from_rules(&strings(&["crate::Point"]), &[]).unwrap();
+        assert_eq!("crate::Point", filter.includes.names[0].as_str());
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use log::info;
+use prettytable::{cell, format, row, Table};
+use serde::Deserialize;
+use structopt::StructOpt;
+use structopt_toml::StructOptToml;
+
+use crate::cli::output::{Output, OutputOpts};
+use crate::cli::ParseOpts;
+use crate::file_filter::FileFilter;
+use crate::segment::segment;
+use crate::segment::stop_words::{STOP_WORDS, TECH_STOP_WORDS};
+use crate::{by_dir, check_ctags, ClassInfo, ParseOption};
+
+#[derive(StructOpt, StructOptToml, Deserialize, Debug, PartialEq, Clone)]
+#[serde(default = "ConceptOpts::defaults")]
+pub struct ConceptOpts {
+    #[structopt(flatten)]
+    #[serde(flatten)]
+    pub parse_opts: ParseOpts,
+    #[structopt(flatten)]
+    #[serde(flatten)]
+    pub output_opts: OutputOpts,
+}
+
+impl AsRef<ParseOpts> for ConceptOpts {
+    fn as_ref(&self) -> &ParseOpts {
+        &self.parse_opts
+    }
+}
+
+impl ConceptOpts {
+    pub fn defaults() -> ConceptOpts {
+        ConceptOpts::from_iter(&["concepts"])
+    }
+}
+
+pub fn run(opts: ConceptOpts) {
+    let _ = env_logger::try_init();
+    if let Err(err) = check_ctags() {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+
+    info!("parse input {:?} with {:?}", &opts.parse_opts.input, &opts);
+
+    let output = opts.output_opts.output("output");
+    let mut parse_option = opts.parse_opts.to_parse_option();
+    parse_option.coverage_file = opts.parse_opts.coverage_file(&output);
+    let filter = opts.parse_opts.to_file_filter();
+
+    output_by_dir(
+        &parse_option,
+        &filter,
+        &PathBuf::from(&opts.parse_opts.input),
+        &output,
+    );
+}
+
+fn output_by_dir(parse_option: &ParseOption, filter: &FileFilter, dir: &Path, output: &Output) {
+    let classes = by_dir(dir, filter.clone(), parse_option).unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1)
+    });
+    let (word, text) = class_to_identify_map(&classes);
+
+    output.write("_word.csv", &map_to_csv(word));
+    // only one csv can be printed for `--output-name -`, the text csv is always a file
+    output.write_file("_text.csv", &map_to_csv(text));
+}
+
+fn map_to_csv(word: HashMap<String, u32>) -> String {
+    let mut hash_vec: Vec<(&String, &u32)> = word.iter().collect();
+    hash_vec.sort_by(|a, b| b.1.cmp(a.1));
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+    for (key, value) in hash_vec {
+        table.add_row(row![key, value.to_string()]);
+    }
+
+    let mut csv = vec![];
+    table.to_csv(&mut csv).unwrap();
+    String::from_utf8_lossy(&csv).to_string()
+}
+
+fn class_to_identify_map(classes: &Vec<ClassInfo>) -> (HashMap<String, u32>, HashMap<String, u32>) {
+    let mut by_word: HashMap<String, u32> = HashMap::default();
+    let mut by_text: HashMap<String, u32> = HashMap::default();
+    info!("class counts: {:?}", &classes.len());
+
+    let mut methods_counts = 0;
+    for class in classes {
+        count_words(&mut by_word, &class.name);
+        count_text(&mut by_text, &class.name);
+
+        methods_counts += class.methods.len();
+        for method in &class.methods {
+            count_words(&mut by_word, &method.name);
+            count_text(&mut by_text, &method.name);
+        }
+
+        for member in &class.members {
+            count_words(&mut by_word, &member.name);
+            count_text(&mut by_text, &member.name);
+        }
+    }
+
+    info!("methods counts: {:?}", methods_counts);
+    (by_word, by_text)
+}
+
+fn count_text(map: &mut HashMap<String, u32>, var: &str) {
+    let counter = map.entry(var.to_string()).or_insert(0);
+    *counter += 1;
+}
+
+fn count_words(map: &mut HashMap<String, u32>, var: &str) {
+    for word in segment(var) {
+        if STOP_WORDS.contains(&word.to_lowercase().as_str()) {
+            continue;
+        }
+
+        if TECH_STOP_WORDS.contains(&word.to_lowercase().as_str()) {
+            continue;
+        }
+
+        let counter = map.entry(word).or_insert(0);
+        *counter += 1;
+    }
+}
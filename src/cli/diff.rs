@@ -0,0 +1,74 @@
+use std::fmt;
+use std::fs::read;
+use std::path::PathBuf;
+use std::process;
+
+use console::{style, Style};
+use similar::{ChangeTag, TextDiff};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug, PartialEq, Clone)]
+pub struct DiffOpts {
+    /// old file, like `modeling.puml` of the last revision
+    #[structopt(parse(from_os_str))]
+    pub old: PathBuf,
+    /// new file
+    #[structopt(parse(from_os_str))]
+    pub new: PathBuf,
+}
+
+struct Line(Option<usize>);
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            None => write!(f, "    "),
+            Some(idx) => write!(f, "{:<4}", idx + 1),
+        }
+    }
+}
+
+pub fn run(opts: DiffOpts) {
+    let old = read_file(&opts.old);
+    let new = read_file(&opts.new);
+
+    let diff = TextDiff::from_lines(&old, &new);
+
+    for (idx, group) in diff.grouped_ops(3).iter().enumerate() {
+        if idx > 0 {
+            println!("{:-^1$}", "-", 80);
+        }
+        for op in group {
+            for change in diff.iter_inline_changes(op) {
+                let (sign, s) = match change.tag() {
+                    ChangeTag::Delete => ("-", Style::new().red()),
+                    ChangeTag::Insert => ("+", Style::new().green()),
+                    ChangeTag::Equal => (" ", Style::new().dim()),
+                };
+                print!(
+                    "{}{} |{}",
+                    style(Line(change.old_index())).dim(),
+                    style(Line(change.new_index())).dim(),
+                    s.apply_to(sign).bold(),
+                );
+                for (emphasized, value) in change.iter_strings_lossy() {
+                    if emphasized {
+                        print!("{}", s.apply_to(value).underlined().on_black());
+                    } else {
+                        print!("{}", s.apply_to(value));
+                    }
+                }
+                if change.missing_newline() {
+                    println!();
+                }
+            }
+        }
+    }
+}
+
+fn read_file(path: &PathBuf) -> Vec<u8> {
+    read(path).unwrap_or_else(|err| {
+        eprintln!("error: failed to read {}: {}", path.display(), err);
+        process::exit(1)
+    })
+}
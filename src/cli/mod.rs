@@ -0,0 +1,343 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::Path;
+use std::process;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use structopt::clap::{AppSettings, ArgMatches};
+use structopt::StructOpt;
+use structopt_toml::StructOptToml;
+
+use crate::class_filter::ClassFilter;
+use crate::cli::output::Output;
+use crate::config::read_config;
+use crate::ctags::ctags_cache::CACHE_DIR;
+use crate::ctags::ctags_lang::CtagsLanguage;
+use crate::file_filter::FileFilter;
+use crate::ParseOption;
+
+pub mod concepts;
+pub mod diff;
+pub mod model;
+pub mod output;
+pub mod render;
+pub mod serve;
+
+/// Modeling is a tools to analysis different languages by Ctags
+// `concepting`, `diffing` and `visualing` are aliases of `concepts`, `diff` and `serve`
+#[derive(StructOpt, Debug)]
+#[structopt(name = "modeling", setting = AppSettings::SubcommandRequiredElseHelp)]
+pub enum Command {
+    /// generate model from source code
+    Model(model::ModelOpts),
+    /// generate concepts from source code
+    Concepts(concepts::ConceptOpts),
+    /// show differences of two files, like outputs of two revisions
+    Diff(diff::DiffOpts),
+    /// visualization the uml of `output.json` in browser
+    Serve(serve::ServeOpts),
+    /// render models exported by `model --export-model` or `--debug`
+    Render(render::RenderOpts),
+}
+
+/// Options to find and parse source code, shared by `model` and `concepts`.
+#[derive(StructOpt, StructOptToml, Deserialize, Debug, PartialEq, Clone)]
+#[serde(default = "ParseOpts::defaults")]
+pub struct ParseOpts {
+    /// input dir
+    #[structopt(short, long, default_value = ".")]
+    pub input: String,
+    /// filter by packages, like: `com.phodal.modeling`
+    #[structopt(long, short, use_delimiter = true)]
+    pub packages: Vec<String>,
+    /// filter by suffixes, like: `java` for .java file
+    #[structopt(long, short, use_delimiter = true)]
+    pub suffixes: Vec<String>,
+    /// by grep regex rules: for example: `.*Service`
+    #[structopt(short, long, default_value = "")]
+    pub grep: String,
+    /// include files by glob relative to the input, like: `src/**`, and exclude by a negated one, like: `!**/generated/**`
+    #[structopt(long, number_of_values = 1)]
+    pub include: Vec<String>,
+    /// exclude files by glob, like: `**/test/**` or `*Test.java`, `!pattern` to include back
+    #[structopt(long, number_of_values = 1)]
+    pub exclude: Vec<String>,
+    /// only analyse files whose content match the regex, like: `@Entity`
+    #[structopt(long, default_value = "")]
+    pub content: String,
+    /// keep classes by rules: `name:.*Service`, `package:<regex>`, `lang:Java`, `kind:struct`, `annotation:Entity`
+    #[structopt(long, number_of_values = 1)]
+    pub class: Vec<String>,
+    /// remove classes matched any rule, same format as `--class`
+    #[structopt(name = "exclude-class", long, number_of_values = 1)]
+    pub exclude_class: Vec<String>,
+    /// parse all files by ctags without the cache in `.modeling/cache` of input dir
+    #[structopt(name = "no-cache", long)]
+    pub no_cache: bool,
+    /// number of ctags processes run at once, 0 for the number of CPUs
+    #[structopt(long, default_value = "0")]
+    pub threads: usize,
+    /// seconds before a ctags process is killed, 0 to wait until it exits
+    #[structopt(long, default_value = "300")]
+    pub timeout: u64,
+    /// write tag lines dropped by the parser to `<name>_coverage.json`, with a summary
+    #[structopt(name = "report-coverage", long)]
+    pub report_coverage: bool,
+    /// load options from the toml file, default: `.modeling.toml` in input dir
+    #[structopt(long, default_value = "")]
+    pub config: String,
+    /// extra ctags options and regex languages in `[languages.<name>]` of config
+    #[structopt(skip)]
+    pub languages: BTreeMap<String, CtagsLanguage>,
+}
+
+impl ParseOpts {
+    /// Defaults of options missing in config, `Default` of `StructOptToml` parses the args of
+    /// process which belong to a command.
+    pub fn defaults() -> ParseOpts {
+        ParseOpts::from_iter(&["modeling"])
+    }
+
+    /// Returns the cache dir in the input dir, so runs from other dirs share the cache.
+    fn cache_dir(&self) -> String {
+        let input = Path::new(&self.input);
+        let dir = match input.parent() {
+            Some(parent) if input.is_file() => parent,
+            _ => input,
+        };
+        dir.join(CACHE_DIR).display().to_string()
+    }
+
+    /// Returns the options of parser, `model` sets the options of diagrams by `DiagramOpts`.
+    pub fn to_parse_option(&self) -> ParseOption {
+        for (name, language) in &self.languages {
+            if let Err(err) = language.validate(name) {
+                eprintln!("error: {}", err);
+                process::exit(1)
+            }
+        }
+        ParseOption {
+            class_filter: ClassFilter::from_rules(&self.class, &self.exclude_class).unwrap_or_else(
+                |err| {
+                    eprintln!("error: {}", err);
+                    process::exit(1)
+                },
+            ),
+            cache_dir: if self.no_cache {
+                "".to_string()
+            } else {
+                self.cache_dir()
+            },
+            threads: self.threads,
+            timeout: self.timeout,
+            languages: self.languages.clone(),
+            ..ParseOption::default()
+        }
+    }
+
+    pub fn to_file_filter(&self) -> FileFilter {
+        FileFilter::new(
+            self.packages.clone(),
+            self.suffixes.clone(),
+            self.grep.clone(),
+        )
+        .with_includes(self.include.clone())
+        .with_excludes(self.exclude.clone())
+        .with_content(self.content.clone())
+    }
+
+    /// Returns the file of the coverage report next to `output`, if reported.
+    pub fn coverage_file(&self, output: &Output) -> String {
+        if self.report_coverage {
+            output.path("_coverage.json").display().to_string()
+        } else {
+            "".to_string()
+        }
+    }
+}
+
+/// Options of diagrams and of simplifying classes in them, shared by `model` and `render`.
+#[derive(StructOpt, StructOptToml, Deserialize, Debug, PartialEq, Clone)]
+#[serde(default = "DiagramOpts::defaults")]
+pub struct DiagramOpts {
+    /// support: puml, mermaid, graphviz with json
+    #[structopt(name = "output-type", short, long, default_value = "puml")]
+    pub output_type: String,
+    /// deduplicate true duplicates only, methods of the same name and parameter types
+    #[structopt(short, long)]
+    pub merge: bool,
+    /// merge overloaded methods into one entry with the count of signatures
+    #[structopt(name = "merge-overloads", long)]
+    pub merge_overloads: bool,
+    /// only load field in methods
+    #[structopt(name = "field-only", short, long)]
+    pub field_only: bool,
+    /// without class inheritance
+    #[structopt(name = "without-parent", long)]
+    pub without_parent: bool,
+    /// if class's prop start with `IRepository` will become `Repository`
+    #[structopt(name = "without-impl-suffix", long)]
+    pub without_impl_suffix: bool,
+    /// if class's prop end with Id and class in list, will replace `int` type to `xxClass`
+    #[structopt(name = "inline-id-suffix", long)]
+    pub inline_id_suffix: bool,
+    /// remove specify suffix by text, for example `DemoDto` with be `Demo`
+    #[structopt(name = "without-suffix", long, default_value = "")]
+    pub without_suffix: String,
+}
+
+impl DiagramOpts {
+    pub fn defaults() -> DiagramOpts {
+        DiagramOpts::from_iter(&["modeling"])
+    }
+
+    /// Returns `option` with the options of diagrams.
+    pub fn with_parse_option(&self, option: ParseOption) -> ParseOption {
+        ParseOption {
+            merge_method_name: self.merge,
+            merge_overloads: self.merge_overloads,
+            field_only: self.field_only,
+            without_parent: self.without_parent,
+            without_impl_suffix: self.without_impl_suffix,
+            inline_id_suffix: self.inline_id_suffix,
+            without_suffix: self.without_suffix.clone(),
+            ..option
+        }
+    }
+}
+
+/// Runs the command in `args`, `model` if args start with its options, like
+/// `modeling --input=src`.
+pub fn run<I: IntoIterator<Item = OsString>>(args: I) {
+    let matches = Command::clap().get_matches_from(with_command(args.into_iter().collect()));
+    match matches.subcommand() {
+        ("model", Some(matches)) => model::run(load_opts(matches)),
+        ("concepts", Some(matches)) => concepts::run(load_opts(matches)),
+        ("diff", Some(matches)) => diff::run(diff::DiffOpts::from_clap(matches)),
+        ("serve", Some(matches)) => serve::run(serve::ServeOpts::from_clap(matches)),
+        ("render", Some(matches)) => render::run(render::RenderOpts::from_clap(matches)),
+        _ => unreachable!("subcommand is required"),
+    }
+}
+
+/// Runs `command` with `args` of an alias, like `concepting --input=src`.
+pub fn run_as<I: IntoIterator<Item = OsString>>(command: &str, args: I) {
+    let mut args = args.into_iter();
+    let mut with_command: Vec<OsString> = args.next().into_iter().collect();
+    with_command.push(command.into());
+    with_command.extend(args);
+    run(with_command)
+}
+
+fn with_command(mut args: Vec<OsString>) -> Vec<OsString> {
+    let is_model = match args.get(1).and_then(|arg| arg.to_str()) {
+        None => true,
+        Some("-h") | Some("--help") | Some("-V") | Some("--version") => false,
+        Some(arg) => arg.starts_with('-'),
+    };
+    if is_model {
+        args.insert(1.min(args.len()), "model".into());
+    }
+    args
+}
+
+/// Returns options of a command with the defaults from config, flags in command line override
+/// the config.
+fn load_opts<T>(matches: &ArgMatches) -> T
+where
+    T: StructOpt + StructOptToml + DeserializeOwned + AsRef<ParseOpts>,
+{
+    let opts = T::from_clap(matches);
+    let parse_opts = opts.as_ref();
+    let config = read_config(&parse_opts.input, &parse_opts.config).unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1)
+    });
+    if config.is_empty() {
+        return opts;
+    }
+
+    T::from_clap_with_toml(&config, matches).unwrap_or_else(|err| {
+        eprintln!("error: failed to parse config: {}", err);
+        process::exit(1)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::path::Path;
+
+    use structopt::StructOpt;
+    use structopt_toml::StructOptToml;
+
+    use crate::cli::model::ModelOpts;
+    use crate::cli::{with_command, Command, ParseOpts};
+    use crate::ctags::ctags_cache::CACHE_DIR;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(|arg| arg.into()).collect()
+    }
+
+    #[test]
+    fn should_run_model_by_default() {
+        assert_eq!(
+            args(&["modeling", "model"]),
+            with_command(args(&["modeling"]))
+        );
+        assert_eq!(
+            args(&["modeling", "model", "--input=src"]),
+            with_command(args(&["modeling", "--input=src"]))
+        );
+        assert_eq!(
+            args(&["modeling", "diff", "a", "b"]),
+            with_command(args(&["modeling", "diff", "a", "b"]))
+        );
+        assert_eq!(
+            args(&["modeling", "--help"]),
+            with_command(args(&["modeling", "--help"]))
+        );
+    }
+
+    #[test]
+    fn should_load_shared_options_of_command_from_config() {
+        let matches =
+            Command::clap().get_matches_from(["modeling", "model", "--grep", ".*Service"]);
+        let matches = matches.subcommand_matches("model").unwrap();
+        let config = r#"
+field_only = true
+grep = ".*Controller"
+exclude = ["**/test/**"]
+
+[languages.Java]
+options = ["--kinds-Java=+l"]
+"#;
+
+        let opts = ModelOpts::from_clap_with_toml(config, matches).unwrap();
+
+        assert!(opts.diagram_opts.field_only);
+        assert_eq!("puml", opts.diagram_opts.output_type);
+        assert_eq!(".*Service", opts.parse_opts.grep);
+        assert_eq!(vec!["**/test/**"], opts.parse_opts.exclude);
+        assert_eq!(".", opts.parse_opts.input);
+        assert_eq!(300, opts.parse_opts.timeout);
+        assert!(opts.parse_opts.languages.contains_key("Java"));
+    }
+
+    #[test]
+    fn should_keep_cache_in_input_dir() {
+        let mut opts = ParseOpts::defaults();
+        opts.input = "src".to_string();
+        assert_eq!(
+            Path::new("src").join(CACHE_DIR).display().to_string(),
+            opts.to_parse_option().cache_dir
+        );
+
+        opts.input = "src/lib.rs".to_string();
+        assert_eq!(
+            Path::new("src").join(CACHE_DIR).display().to_string(),
+            opts.to_parse_option().cache_dir
+        );
+    }
+}
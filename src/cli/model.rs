@@ -0,0 +1,441 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use ignore::WalkBuilder;
+use serde::Deserialize;
+use structopt::StructOpt;
+use structopt_toml::StructOptToml;
+
+use crate::class_filter::ClassFilter;
+use crate::cli::output::{Output, OutputOpts};
+use crate::cli::{DiagramOpts, ParseOpts};
+use crate::config::ModuleConfig;
+use crate::ctags::ctags_parser::CtagsParser;
+use crate::document::ModelDocument;
+use crate::file_filter::FileFilter;
+use crate::render::graphviz_render::GraphvizRender;
+use crate::render::{DependencyRender, MermaidRender, ModuleRender, PlantUmlRender};
+use crate::source::dependency::DependencyEdge;
+use crate::workspace::{self, detect_modules, Module, ModuleGraph};
+use crate::{
+    by_dir, by_git, by_modules, by_rev, check_ctags, deps_by_dir, deps_by_rev, ClassInfo, Opt,
+    ParseOption,
+};
+
+#[derive(StructOpt, StructOptToml, Deserialize, Debug, PartialEq, Clone)]
+#[serde(default = "ModelOpts::defaults")]
+pub struct ModelOpts {
+    /// output debug information
+    #[structopt(short, long)]
+    pub debug: bool,
+    /// multiple modules, detected from Maven, Gradle, Cargo, Go and npm workspaces or top level dirs
+    #[structopt(name = "by-modules", short, long)]
+    pub by_modules: bool,
+    /// export the model with relations to `<name>_model.json`, see `schema/model.schema.json`
+    #[structopt(name = "export-model", long)]
+    pub export_model: bool,
+    /// output dependencies from imports, support: file, package
+    #[structopt(long, default_value = "")]
+    pub deps: String,
+    /// list files by `git ls-files` instead of walking the dir
+    #[structopt(long)]
+    pub git: bool,
+    /// with `--git`, include untracked files
+    #[structopt(name = "include-untracked", long)]
+    pub include_untracked: bool,
+    /// with `--git`, include ignored files
+    #[structopt(name = "include-ignored", long)]
+    pub include_ignored: bool,
+    /// with `--git`, include files of submodules
+    #[structopt(name = "include-submodule", long)]
+    pub include_submodule: bool,
+    /// with `--git`, exclude files tracked by git-lfs
+    #[structopt(name = "exclude-lfs", long)]
+    pub exclude_lfs: bool,
+    /// analyse a git revision without checkout, like: `v0.6.2` or `HEAD~1`
+    #[structopt(long, default_value = "")]
+    pub rev: String,
+    #[structopt(flatten)]
+    #[serde(flatten)]
+    pub parse_opts: ParseOpts,
+    #[structopt(flatten)]
+    #[serde(flatten)]
+    pub diagram_opts: DiagramOpts,
+    #[structopt(flatten)]
+    #[serde(flatten)]
+    pub output_opts: OutputOpts,
+    /// settings of modules in `[modules.<name>]` of config
+    #[structopt(skip)]
+    pub modules: HashMap<String, ModuleConfig>,
+}
+
+impl AsRef<ParseOpts> for ModelOpts {
+    fn as_ref(&self) -> &ParseOpts {
+        &self.parse_opts
+    }
+}
+
+impl ModelOpts {
+    pub fn defaults() -> ModelOpts {
+        ModelOpts::from_iter(&["model"])
+    }
+
+    pub fn to_parse_option(&self) -> ParseOption {
+        self.diagram_opts
+            .with_parse_option(self.parse_opts.to_parse_option())
+    }
+
+    /// Returns options of the module with its settings in config, or `None` if skipped.
+    pub fn for_module(&self, name: &str) -> Option<ModelOpts> {
+        let mut opts = self.clone();
+        let module = match self.modules.get(name) {
+            Some(module) => module,
+            None => return Some(opts),
+        };
+        if module.skip {
+            return None;
+        }
+
+        fn set<T: Clone>(value: &mut T, module_value: &Option<T>) {
+            if let Some(module_value) = module_value {
+                *value = module_value.clone();
+            }
+        }
+
+        let parse_opts = &mut opts.parse_opts;
+        set(&mut parse_opts.packages, &module.packages);
+        set(&mut parse_opts.suffixes, &module.suffixes);
+        set(&mut parse_opts.grep, &module.grep);
+        set(&mut parse_opts.content, &module.content);
+        parse_opts.include.extend(module.include.iter().cloned());
+        parse_opts.exclude.extend(module.exclude.iter().cloned());
+        parse_opts.class.extend(module.class.iter().cloned());
+        parse_opts
+            .exclude_class
+            .extend(module.exclude_class.iter().cloned());
+
+        let diagram_opts = &mut opts.diagram_opts;
+        set(&mut diagram_opts.output_type, &module.output_type);
+        set(&mut diagram_opts.merge, &module.merge);
+        set(&mut diagram_opts.merge_overloads, &module.merge_overloads);
+        set(&mut diagram_opts.field_only, &module.field_only);
+        set(&mut diagram_opts.without_parent, &module.without_parent);
+        set(
+            &mut diagram_opts.without_impl_suffix,
+            &module.without_impl_suffix,
+        );
+        set(&mut diagram_opts.inline_id_suffix, &module.inline_id_suffix);
+        set(&mut diagram_opts.without_suffix, &module.without_suffix);
+        Some(opts)
+    }
+}
+
+pub fn run(opts: ModelOpts) {
+    if let Err(err) = check_ctags() {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+    check_deps(&opts);
+
+    let output = opts.output_opts.output("modeling");
+    if !opts.by_modules {
+        let mut parse_option = opts.to_parse_option();
+        parse_option.coverage_file = opts.parse_opts.coverage_file(&output);
+        let filter = opts.parse_opts.to_file_filter();
+        output_all_in_one(&opts, &parse_option, filter, &output);
+        return;
+    }
+
+    let mut modules = vec![];
+    let mut module_opts = vec![];
+    for module in load_modules(&opts.parse_opts.input) {
+        if let Some(opts) = opts.for_module(&module.output_name()) {
+            check_deps(&opts);
+            modules.push(module);
+            module_opts.push(opts);
+        }
+    }
+
+    let classes = load_modules_model(&opts, &modules, &module_opts, &output);
+    let mut modeled = vec![];
+    for (module, module_opts) in modules.iter().zip(&module_opts) {
+        let parse_option = module_opts.to_parse_option();
+        let module_classes = classes_of_module(&classes, module, &parse_option);
+        output_by_dir(
+            module_opts,
+            &module.path,
+            &module_classes,
+            &parse_option,
+            &output.named(&module.output_name()),
+        );
+        modeled.extend(module_classes);
+    }
+
+    output_modules(&opts, &modeled, &output);
+}
+
+/// Returns classes of all modules in one model, parsed once with files of each module filtered by
+/// its settings. Rules of classes and merging of declarations differ by modules, so they are
+/// applied by `classes_of_module`.
+fn load_modules_model(
+    opts: &ModelOpts,
+    modules: &[Module],
+    module_opts: &[ModelOpts],
+    output: &Output,
+) -> Vec<ClassInfo> {
+    let mut parse_option = opts.to_parse_option();
+    parse_option.class_filter = ClassFilter::default();
+    parse_option.merge_method_name = false;
+    parse_option.merge_overloads = false;
+    parse_option.coverage_file = opts.parse_opts.coverage_file(output);
+    let filters: Vec<FileFilter> = module_opts
+        .iter()
+        .map(|opts| opts.parse_opts.to_file_filter())
+        .collect();
+
+    if opts.git || !opts.rev.is_empty() {
+        // files of git are relative to the input, so are paths of modules to tag classes
+        let input = Path::new(&opts.parse_opts.input);
+        let relative: Vec<Module> = modules
+            .iter()
+            .map(|module| {
+                let path = module.path.strip_prefix(input).unwrap_or(&module.path);
+                Module::new(&module.name, path, &module.build)
+            })
+            .collect();
+        let mut classes =
+            load_classes(opts, input, opts.parse_opts.to_file_filter(), &parse_option);
+        classes.retain(|clazz| {
+            let file = Path::new(&clazz.file);
+            workspace::module_of(file, &relative)
+                .and_then(|module| relative.iter().position(|it| it == module))
+                .is_some_and(|index| filters[index].allow_path(file))
+        });
+        workspace::tag_modules(&mut classes, &relative);
+        return classes;
+    }
+
+    by_modules(&opts.parse_opts.input, modules, &filters, &parse_option).unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1)
+    })
+}
+
+/// Returns classes of `module` in the model of all modules, filtered and merged by its options.
+fn classes_of_module(
+    classes: &[ClassInfo],
+    module: &Module,
+    parse_option: &ParseOption,
+) -> Vec<ClassInfo> {
+    let module_classes = classes
+        .iter()
+        .filter(|clazz| clazz.module == module.output_name())
+        .cloned()
+        .collect();
+    let mut module_classes = parse_option.class_filter.filter(module_classes);
+    for clazz in module_classes.iter_mut() {
+        CtagsParser::sort_declarations(clazz, parse_option);
+    }
+    module_classes
+}
+
+/// Returns modules declared by build files, or top level dirs of input without workspace.
+fn load_modules(input: &str) -> Vec<Module> {
+    let modules = detect_modules(input);
+    if !modules.is_empty() {
+        return modules;
+    }
+
+    let mut modules = vec![];
+    for dir in WalkBuilder::new(input).max_depth(Some(1)).build().flatten() {
+        let path = dir.path();
+        if path.is_dir() {
+            if let Some(x) = path.file_name() {
+                modules.push(Module::new(x.to_str().unwrap(), path, "dir"));
+            };
+        }
+    }
+    modules
+}
+
+/// Outputs dependencies between modules from the model of all modules.
+fn output_modules(opts: &ModelOpts, classes: &[ClassInfo], output: &Output) {
+    let graph = ModuleGraph::build(classes);
+
+    if opts.debug {
+        output.write_file("_modules.json", &serde_json::to_string(classes).unwrap());
+    }
+
+    match opts.diagram_opts.output_type.as_str() {
+        "mermaid" => output.write("_modules.mermaid", &ModuleRender::mermaid(&graph)),
+        "graphviz" => output.write("_modules.dot", &ModuleRender::graphviz(&graph)),
+        &_ => output.write("_modules.puml", &ModuleRender::plantuml(&graph)),
+    }
+}
+
+/// Outputs the diagram, the model and dependencies of a module.
+fn output_by_dir(
+    opts: &ModelOpts,
+    path: &Path,
+    classes: &Vec<ClassInfo>,
+    parse_option: &ParseOption,
+    output: &Output,
+) {
+    if !classes.is_empty() {
+        output_classes(
+            &opts.diagram_opts.output_type,
+            classes,
+            parse_option,
+            output,
+        );
+    }
+
+    export_model(opts, classes, parse_option, output);
+    output_deps(opts, path, opts.parse_opts.to_file_filter(), output);
+}
+
+fn output_all_in_one(
+    opts: &ModelOpts,
+    parse_option: &ParseOption,
+    filter: FileFilter,
+    output: &Output,
+) {
+    let input = Path::new(&opts.parse_opts.input);
+    let classes = load_classes(opts, input, filter.clone(), parse_option);
+
+    if opts.debug {
+        output.write_in_dir("debug.json", &serde_json::to_string(&classes).unwrap());
+    }
+
+    output_classes(
+        &opts.diagram_opts.output_type,
+        &classes,
+        parse_option,
+        output,
+    );
+    export_model(opts, &classes, parse_option, output);
+    output_deps(opts, input, filter, output);
+}
+
+fn load_classes(
+    opts: &ModelOpts,
+    path: &Path,
+    filter: FileFilter,
+    parse_option: &ParseOption,
+) -> Vec<ClassInfo> {
+    let result = if !opts.rev.is_empty() {
+        by_rev(&git_opt(opts, path), &opts.rev, filter, parse_option)
+    } else if opts.git {
+        by_git(&git_opt(opts, path), filter, parse_option)
+    } else {
+        by_dir(path, filter, parse_option)
+    };
+
+    result.unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1)
+    })
+}
+
+fn git_opt(opts: &ModelOpts, path: &Path) -> Opt {
+    let mut git_opt = Opt::from_iter(["ptags"].iter());
+    git_opt.dir = path.to_path_buf();
+    git_opt.include_untracked = opts.include_untracked;
+    git_opt.include_ignored = opts.include_ignored;
+    git_opt.include_submodule = opts.include_submodule;
+    git_opt.exclude_lfs = opts.exclude_lfs;
+    git_opt
+}
+
+/// Writes the diagram of `classes` in `output_type`, like `modeling.puml`, graphviz also writes
+/// `output.json` for the visualization.
+pub fn output_classes(
+    output_type: &str,
+    classes: &Vec<ClassInfo>,
+    parse_option: &ParseOption,
+    output: &Output,
+) {
+    match output_type {
+        "mermaid" => output.write(".mermaid", &MermaidRender::render(classes, parse_option)),
+        "graphviz" => {
+            let (graph, data) = GraphvizRender::render_with_data(classes, parse_option);
+            output.write(".dot", &graph);
+            output.write_in_dir("output.json", &serde_json::to_string(&data).unwrap());
+        }
+        &_ => output.write(".puml", &PlantUmlRender::render(classes, parse_option)),
+    }
+}
+
+/// Writes the model document of `classes`, their files are relative to the input which is the
+/// `source_root` of the document, also for modules. The coverage is added if reported.
+fn export_model(
+    opts: &ModelOpts,
+    classes: &[ClassInfo],
+    parse_option: &ParseOption,
+    output: &Output,
+) {
+    if !opts.export_model {
+        return;
+    }
+
+    let input = Path::new(&opts.parse_opts.input);
+    let mut classes = classes.to_vec();
+    for clazz in classes.iter_mut() {
+        clazz.file = clazz.relative_file(input);
+    }
+    let root = fs::canonicalize(input).unwrap_or_else(|_| input.to_path_buf());
+    let mut document = ModelDocument::new(&root.display().to_string(), classes);
+    if !parse_option.coverage_file.is_empty() {
+        document.diagnostics = fs::read_to_string(&parse_option.coverage_file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok());
+    }
+    output.write_file(
+        "_model.json",
+        &serde_json::to_string_pretty(&document).unwrap(),
+    );
+}
+
+/// Stops before parsing if `--deps` is not supported, instead of skipping the output silently.
+fn check_deps(opts: &ModelOpts) {
+    match opts.deps.as_str() {
+        "" | "file" | "package" => {}
+        deps => {
+            eprintln!("error: unsupported deps {}, support: file, package", deps);
+            process::exit(1)
+        }
+    }
+}
+
+fn output_deps<P: AsRef<Path>>(opts: &ModelOpts, path: P, filter: FileFilter, output: &Output) {
+    if opts.deps.is_empty() {
+        return;
+    }
+
+    let graph = if opts.rev.is_empty() {
+        deps_by_dir(path, filter)
+    } else {
+        let path = path.as_ref();
+        deps_by_rev(&git_opt(opts, path), &opts.rev, filter).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(1)
+        })
+    };
+    let edges: &Vec<DependencyEdge> = match opts.deps.as_str() {
+        "file" => &graph.file_edges,
+        "package" => &graph.package_edges,
+        deps => unreachable!("unsupported deps {}", deps),
+    };
+
+    if opts.debug {
+        output.write_file("_deps.json", &serde_json::to_string(&graph).unwrap());
+    }
+
+    match opts.diagram_opts.output_type.as_str() {
+        "mermaid" => output.write_file("_deps.mermaid", &DependencyRender::mermaid(edges)),
+        "graphviz" => output.write_file("_deps.dot", &DependencyRender::graphviz(edges)),
+        &_ => output.write_file("_deps.puml", &DependencyRender::plantuml(edges)),
+    }
+}
@@ -0,0 +1,166 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use serde::Deserialize;
+use structopt::StructOpt;
+use structopt_toml::StructOptToml;
+
+/// name of output to print the main output to stdout
+pub const STDOUT: &str = "-";
+
+/// Options of where outputs go, shared by commands which write files.
+#[derive(StructOpt, StructOptToml, Deserialize, Debug, PartialEq, Clone)]
+#[serde(default = "OutputOpts::defaults")]
+pub struct OutputOpts {
+    /// dir of output files, created if missing
+    #[structopt(name = "output-dir", long, default_value = ".")]
+    pub output_dir: String,
+    /// name of output files without extension, like `modeling` for `modeling.puml`, `-` to print the main output to stdout
+    #[structopt(name = "output-name", long, default_value = "")]
+    pub output_name: String,
+}
+
+impl OutputOpts {
+    pub fn defaults() -> OutputOpts {
+        OutputOpts::from_iter(&["modeling"])
+    }
+
+    /// Returns the output named by `--output-name`, or by `default_name` of the command.
+    pub fn output(&self, default_name: &str) -> Output {
+        let name = if self.output_name.is_empty() {
+            default_name
+        } else {
+            &self.output_name
+        };
+        Output {
+            dir: PathBuf::from(&self.output_dir),
+            name: name.to_string(),
+            default_name: default_name.to_string(),
+        }
+    }
+}
+
+/// Files of a command named by `name` in `dir`, like `modeling.puml` and `modeling_deps.puml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+    dir: PathBuf,
+    name: String,
+    /// name of the other files when the main output is printed to stdout
+    default_name: String,
+}
+
+impl Output {
+    /// Returns the output of another name in the same dir, like outputs of modules.
+    pub fn named(&self, name: &str) -> Output {
+        Output {
+            dir: self.dir.clone(),
+            name: name.to_string(),
+            default_name: name.to_string(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the path of the file named with `suffix`, like `out/modeling_deps.puml` for
+    /// `_deps.puml`, the default name is used if the main output is printed to stdout.
+    pub fn path(&self, suffix: &str) -> PathBuf {
+        let name = if self.name == STDOUT {
+            &self.default_name
+        } else {
+            &self.name
+        };
+        self.dir.join(format!("{}{}", name, suffix))
+    }
+
+    /// Writes the main output, like the diagram, to the file named with `suffix`, or prints it
+    /// for `--output-name -`.
+    pub fn write(&self, suffix: &str, content: &str) {
+        if self.name != STDOUT {
+            self.write_file(suffix, content);
+            return;
+        }
+
+        let mut stdout = io::stdout();
+        let result = stdout
+            .write_all(content.as_bytes())
+            .and_then(|_| stdout.write_all(b"\n"));
+        if let Err(err) = result {
+            eprintln!("error: failed to print output: {}", err);
+            process::exit(1)
+        }
+    }
+
+    /// Writes other outputs, like dependencies or debug information, to the file named with
+    /// `suffix`.
+    pub fn write_file(&self, suffix: &str, content: &str) {
+        self.save(&self.path(suffix), content)
+    }
+
+    /// Writes a file of fixed name in the dir, like `output.json` of the visualization.
+    pub fn write_in_dir(&self, file_name: &str, content: &str) {
+        self.save(&self.dir.join(file_name), content)
+    }
+
+    fn save(&self, path: &Path, content: &str) {
+        let result = fs::create_dir_all(&self.dir).and_then(|_| fs::write(path, content));
+        if let Err(err) = result {
+            eprintln!("error: failed to write {}: {}", path.display(), err);
+            process::exit(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use structopt::StructOpt;
+
+    use crate::cli::output::OutputOpts;
+
+    #[test]
+    fn should_name_outputs_by_command_by_default() {
+        let output = OutputOpts::from_iter(&["modeling"]).output("modeling");
+        assert_eq!(PathBuf::from("./modeling.puml"), output.path(".puml"));
+
+        let opts = OutputOpts::from_iter(&["modeling", "--output-dir=out", "--output-name=shop"]);
+        let output = opts.output("modeling");
+        assert_eq!(
+            PathBuf::from("out/shop_deps.puml"),
+            output.path("_deps.puml")
+        );
+        assert_eq!(
+            PathBuf::from("out/order.puml"),
+            output.named("order").path(".puml")
+        );
+    }
+
+    #[test]
+    fn should_name_other_files_by_default_for_stdout() {
+        let opts = OutputOpts::from_iter(&["modeling", "--output-name", "-"]);
+        let output = opts.output("modeling");
+
+        assert_eq!("-", output.name());
+        assert_eq!(PathBuf::from("./modeling.json"), output.path(".json"));
+    }
+
+    #[test]
+    fn should_create_output_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_dir = dir.path().join("out").join("uml");
+        let opts = OutputOpts {
+            output_dir: output_dir.to_str().unwrap().to_string(),
+            output_name: "".to_string(),
+        };
+
+        opts.output("modeling").write(".puml", "@startuml\n@enduml");
+
+        let content = fs::read_to_string(output_dir.join("modeling.puml")).unwrap();
+        assert_eq!("@startuml\n@enduml", content);
+    }
+}
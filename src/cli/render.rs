@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use structopt::StructOpt;
+
+use crate::class_filter::ClassFilter;
+use crate::cli::model::output_classes;
+use crate::cli::output::OutputOpts;
+use crate::cli::DiagramOpts;
+use crate::ctags::ctags_parser::CtagsParser;
+use crate::document::read_classes;
+use crate::{ClassInfo, ParseOption};
+
+#[derive(StructOpt, Debug, PartialEq, Clone)]
+pub struct RenderOpts {
+    /// models exported by `model --export-model`, or saved by `--debug` like `debug.json`, classes
+    /// of several models are merged
+    #[structopt(parse(from_os_str), required = true, min_values = 1)]
+    pub models: Vec<PathBuf>,
+    /// keep classes by rules: `name:.*Service`, `package:<regex>`, `lang:Java`, `kind:struct`, `annotation:Entity`
+    #[structopt(long, number_of_values = 1)]
+    pub class: Vec<String>,
+    /// remove classes matched any rule, same format as `--class`
+    #[structopt(name = "exclude-class", long, number_of_values = 1)]
+    pub exclude_class: Vec<String>,
+    #[structopt(flatten)]
+    pub diagram_opts: DiagramOpts,
+    #[structopt(flatten)]
+    pub output_opts: OutputOpts,
+}
+
+impl RenderOpts {
+    pub fn to_parse_option(&self) -> ParseOption {
+        let class_filter = ClassFilter::from_rules(&self.class, &self.exclude_class)
+            .unwrap_or_else(|err| {
+                eprintln!("error: {}", err);
+                process::exit(1)
+            });
+        self.diagram_opts.with_parse_option(ParseOption {
+            class_filter,
+            ..ParseOption::default()
+        })
+    }
+}
+
+pub fn run(opts: RenderOpts) {
+    let mut models = vec![];
+    for path in &opts.models {
+        let classes = read_model(path).unwrap_or_else(|err| {
+            eprintln!("error: failed to read model {}: {}", path.display(), err);
+            process::exit(1)
+        });
+        models.push(classes);
+    }
+
+    let parse_option = opts.to_parse_option();
+    let classes = merge_models(models, &parse_option);
+    output_classes(
+        &opts.diagram_opts.output_type,
+        &classes,
+        &parse_option,
+        &opts.output_opts.output("modeling"),
+    );
+}
+
+fn read_model(path: &PathBuf) -> Result<Vec<ClassInfo>, failure::Error> {
+    read_classes(&fs::read_to_string(path)?)
+}
+
+/// language, package and name of a class
+type ClassKey = (String, String, String);
+
+/// Returns classes of all models filtered and simplified by `parse_option`, a class in several
+/// models, like a partial class or models of the same code, is merged into one. Classes of the
+/// same name in one model, like in different files, are kept apart.
+fn merge_models(models: Vec<Vec<ClassInfo>>, parse_option: &ParseOption) -> Vec<ClassInfo> {
+    // merged classes of a name, with indexes of the models merged into each of them
+    let mut class_map: BTreeMap<ClassKey, Vec<(Vec<usize>, ClassInfo)>> = BTreeMap::new();
+    for (model, classes) in models.into_iter().enumerate() {
+        for mut clazz in classes {
+            if clazz.id.is_empty() {
+                clazz.id = clazz.stable_id(Path::new(""));
+            }
+            let key = (
+                clazz.lang.clone(),
+                clazz.package.clone(),
+                clazz.name.clone(),
+            );
+            let same_named = class_map.entry(key).or_default();
+            match same_named
+                .iter_mut()
+                .find(|(models, _)| !models.contains(&model))
+            {
+                Some((models, merged)) => {
+                    models.push(model);
+                    merge_class(merged, clazz);
+                }
+                None => same_named.push((vec![model], clazz)),
+            }
+        }
+    }
+
+    let merged = class_map
+        .into_values()
+        .flatten()
+        .map(|(_, clazz)| clazz)
+        .collect();
+    let mut classes = parse_option.class_filter.filter(merged);
+    for clazz in classes.iter_mut() {
+        CtagsParser::sort_declarations(clazz, parse_option);
+    }
+    classes.sort_by_key(|clazz| clazz.name.to_lowercase());
+    classes
+}
+
+fn merge_class(merged: &mut ClassInfo, clazz: ClassInfo) {
+    for parent in clazz.parents {
+        if !merged.parents.contains(&parent) {
+            merged.parents.push(parent);
+        }
+    }
+    for annotation in clazz.annotations {
+        if !merged.annotations.contains(&annotation) {
+            merged.annotations.push(annotation);
+        }
+    }
+    for member in clazz.members {
+        let exists = merged
+            .members
+            .iter()
+            .any(|it| it.name == member.name && it.data_type == member.data_type);
+        if !exists {
+            merged.members.push(member);
+        }
+    }
+    for method in clazz.methods {
+        let exists = merged
+            .methods
+            .iter()
+            .any(|it| it.name == method.name && it.parameters == method.parameters);
+        if !exists {
+            merged.methods.push(method);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::render::merge_models;
+    use crate::testing::class;
+    use crate::ParseOption;
+
+    #[test]
+    fn should_merge_classes_of_models() {
+        let first = vec![
+            class("Java", "", "Order")
+                .with_members(&["id"])
+                .with_methods(&["pay"]),
+            class("Java", "", "Item"),
+        ];
+        let second = vec![class("Java", "", "Order")
+            .with_members(&["id", "amount"])
+            .with_methods(&["pay", "cancel"])];
+
+        let classes = merge_models(vec![first, second], &ParseOption::default());
+
+        assert_eq!(2, classes.len());
+        assert_eq!("Item", classes[0].name);
+        let order = &classes[1];
+        let members: Vec<&str> = order.members.iter().map(|it| it.name.as_str()).collect();
+        assert_eq!(vec!["amount", "id"], members);
+        let methods: Vec<&str> = order.methods.iter().map(|it| it.name.as_str()).collect();
+        assert_eq!(vec!["cancel", "pay"], methods);
+    }
+
+    #[test]
+    fn should_keep_classes_of_same_name_in_model_apart() {
+        let mut order = class("Java", "", "Order").with_members(&["id"]);
+        order.file = "api/Order.java".to_string();
+        let mut other = class("Java", "", "Order").with_members(&["amount"]);
+        other.file = "web/Order.java".to_string();
+
+        let classes = merge_models(vec![vec![order, other]], &ParseOption::default());
+
+        assert_eq!(2, classes.len());
+        assert_ne!(classes[0].id, classes[1].id);
+        assert_eq!("id", classes[0].members[0].name);
+        assert_eq!("amount", classes[1].members[0].name);
+    }
+}
@@ -0,0 +1,92 @@
+use std::borrow::Cow;
+use std::fs;
+use std::path::PathBuf;
+
+use actix_web::body::Body;
+use actix_web::{get, web, App, HttpResponse, HttpServer};
+use mime_guess::from_path;
+use rust_embed::RustEmbed;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug, PartialEq, Clone)]
+pub struct ServeOpts {
+    /// port of the local server
+    #[structopt(default_value = "9000")]
+    pub port: String,
+    /// dir of `output.json` written by `model -o graphviz`
+    #[structopt(name = "output-dir", long, default_value = ".")]
+    pub output_dir: String,
+}
+
+pub fn run(opts: ServeOpts) {
+    let result = actix_web::rt::System::new("serve")
+        .block_on(async move { start_local_server(&opts.port, &opts.output_dir).await });
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+async fn start_local_server(port: &str, output_dir: &str) -> std::io::Result<()> {
+    let url = format!("http://127.0.0.1:{}", port);
+    println!("start server: {}", url);
+
+    open_url(&url);
+    start(port, output_dir).await
+}
+
+pub async fn start(port: &str, output_dir: &str) -> std::io::Result<()> {
+    let output_dir = PathBuf::from(output_dir);
+    HttpServer::new(move || {
+        App::new()
+            .data(output_dir.clone())
+            .service(web::resource("/").route(web::get().to(index)))
+            .service(data)
+            .service(web::resource("/{_:.*}").route(web::get().to(dist)))
+    })
+    .bind(format!("127.0.0.1:{}", port))?
+    .run()
+    .await
+}
+
+#[get("/output.json")]
+pub fn data(output_dir: web::Data<PathBuf>) -> HttpResponse {
+    let content = fs::read_to_string(output_dir.join("output.json")).unwrap();
+
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .body(content.into_bytes())
+}
+
+pub fn open_url(url: &str) {
+    if let Err(err) = webbrowser::open(url) {
+        println!("failure to open in browser: {}", err);
+    }
+}
+
+#[derive(RustEmbed)]
+#[folder = "static/"]
+struct Asset;
+
+fn handle_embedded_file(path: &str) -> HttpResponse {
+    match Asset::get(path) {
+        Some(content) => {
+            let body: Body = match content.data {
+                Cow::Borrowed(bytes) => bytes.into(),
+                Cow::Owned(bytes) => bytes.into(),
+            };
+            HttpResponse::Ok()
+                .content_type(from_path(path).first_or_octet_stream().as_ref())
+                .body(body)
+        }
+        None => HttpResponse::NotFound().body("404 Not Found"),
+    }
+}
+
+pub fn index() -> HttpResponse {
+    handle_embedded_file("index.html")
+}
+
+pub fn dist(path: web::Path<String>) -> HttpResponse {
+    handle_embedded_file(&path.0)
+}
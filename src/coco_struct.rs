@@ -1,4 +1,7 @@
-use serde::{Deserialize, Serialize};
+use std::path::{Component, Path};
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MemberInfo {
@@ -7,6 +10,12 @@ pub struct MemberInfo {
     pub data_type: String,
     pub pure_data_type: String,
     pub line_no: i32,
+    /// qualified name of the class `data_type` refers to, see `source::resolve`
+    #[serde(default)]
+    pub qualified_type: String,
+    /// `data_type` can't be resolved to a class of the model, like `String` or `java.util.List`
+    #[serde(default)]
+    pub external: bool,
 }
 
 impl MemberInfo {
@@ -17,6 +26,8 @@ impl MemberInfo {
             data_type,
             pure_data_type: "".to_string(),
             line_no: 0,
+            qualified_type: "".to_string(),
+            external: false,
         }
     }
 }
@@ -29,6 +40,15 @@ pub struct MethodInfo {
     pub return_type: String,
     pub pure_return_type: String,
     pub line_no: i32,
+    /// qualified name of the class `return_type` refers to, see `source::resolve`
+    #[serde(default)]
+    pub qualified_return_type: String,
+    /// `return_type` can't be resolved to a class of the model
+    #[serde(default)]
+    pub external: bool,
+    /// count of overloaded signatures merged into this method, see `ParseOption.merge_overloads`
+    #[serde(default)]
+    pub overloads: usize,
 }
 
 impl MethodInfo {
@@ -40,33 +60,134 @@ impl MethodInfo {
             return_type,
             pure_return_type: "".to_string(),
             line_no: 0,
+            qualified_return_type: "".to_string(),
+            external: false,
+            overloads: 0,
         }
     }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ClassInfo {
-    pub id: i32,
+    /// same between runs, see `ClassInfo::stable_id`
+    #[serde(default, deserialize_with = "deserialize_id")]
+    pub id: String,
     pub name: String,
     pub package: String,
     pub file: String,
     pub lang: String,
+    /// kind of ctags tag, like `class` or `struct`
+    #[serde(default)]
+    pub kind: String,
     pub parents: Vec<String>,
     pub members: Vec<MemberInfo>,
     pub methods: Vec<MethodInfo>,
+    #[serde(default)]
+    pub line_no: i32,
+    /// derives, attributes or annotations declared on the class, like `Serialize` or `Entity`
+    #[serde(default)]
+    pub annotations: Vec<String>,
+    /// name of the module with `--by-modules`, see `workspace::tag_modules`
+    #[serde(default)]
+    pub module: String,
 }
 
 impl ClassInfo {
     pub fn new(class_name: &str) -> Self {
         ClassInfo {
-            id: 0,
+            id: "".to_string(),
             name: class_name.to_string(),
             package: "".to_string(),
             file: "".to_string(),
             lang: "".to_string(),
+            kind: "".to_string(),
             parents: vec![],
             members: vec![],
             methods: vec![],
+            line_no: 0,
+            annotations: vec![],
+            module: "".to_string(),
+        }
+    }
+
+    /// Returns the name with its package, like `com.phodal.Order` or `crate::render::PlantUmlRender`
+    pub fn qualified_name(&self) -> String {
+        if self.package.is_empty() {
+            return self.name.clone();
+        }
+
+        let separator = if self.lang == "Rust" { "::" } else { "." };
+        format!("{}{}{}", self.package, separator, self.name)
+    }
+
+    /// Returns the id by language, qualified name and file relative to `root`, like
+    /// `Java:com.phodal.Order@src/main/java/com/phodal/Order.java`, names can be the same in
+    /// different packages or files.
+    pub fn stable_id(&self, root: &Path) -> String {
+        format!(
+            "{}:{}@{}",
+            self.lang,
+            self.qualified_name(),
+            self.relative_file(root)
+        )
+    }
+
+    /// Returns `file` relative to `root`, the dir of the input, joined by `/`, so it's the same
+    /// for `-i src`, `-i ./src` and the absolute path of `src`.
+    pub fn relative_file(&self, root: &Path) -> String {
+        let file = Path::new(&self.file);
+        file.strip_prefix(root)
+            .unwrap_or(file)
+            .components()
+            .filter(|component| *component != Component::CurDir)
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    pub fn has_annotation(&self, name: &str) -> bool {
+        self.annotations.iter().any(|annotation| annotation == name)
+    }
+}
+
+/// Ids of models saved before ids were assigned are `0`, they are read as empty.
+fn deserialize_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::String(id) => id,
+        _ => "".to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::coco_struct::ClassInfo;
+
+    #[test]
+    fn should_return_file_relative_to_input() {
+        let mut clazz = ClassInfo::new("Order");
+        for (file, input) in [
+            ("src/shop/Order.java", "src"),
+            ("./src/shop/Order.java", "./src"),
+            ("/code/src/shop/Order.java", "/code/src"),
+            ("shop/Order.java", "."),
+        ] {
+            clazz.file = file.to_string();
+            assert_eq!("shop/Order.java", clazz.relative_file(Path::new(input)));
         }
     }
+
+    #[test]
+    fn should_build_same_id_for_paths_of_input() {
+        let mut clazz = ClassInfo::new("Order");
+        clazz.lang = "Java".to_string();
+        clazz.package = "shop".to_string();
+        clazz.file = "./src/shop/Order.java".to_string();
+        let relative = clazz.stable_id(Path::new("./src"));
+        clazz.file = "/code/src/shop/Order.java".to_string();
+
+        assert_eq!("Java:shop.Order@shop/Order.java", relative);
+        assert_eq!(relative, clazz.stable_id(Path::new("/code/src")));
+    }
 }
@@ -0,0 +1,118 @@
+use std::fs;
+use std::path::Path;
+
+use failure::{format_err, Error};
+use serde::{Deserialize, Serialize};
+
+/// project configuration loaded from the input dir when `--config` is not given
+pub const CONFIG_FILE: &str = ".modeling.toml";
+
+/// Settings of a module in `[modules.<name>]` of the configuration, `<name>` is the dir name of
+/// the module with `--by-modules`. Given values override the top level ones, while globs and
+/// class rules are added to them.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ModuleConfig {
+    /// don't output the module
+    pub skip: bool,
+    pub output_type: Option<String>,
+    pub packages: Option<Vec<String>>,
+    pub suffixes: Option<Vec<String>>,
+    pub grep: Option<String>,
+    pub content: Option<String>,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub class: Vec<String>,
+    pub exclude_class: Vec<String>,
+    pub merge: Option<bool>,
+    pub merge_overloads: Option<bool>,
+    pub field_only: Option<bool>,
+    pub without_parent: Option<bool>,
+    pub without_impl_suffix: Option<bool>,
+    pub inline_id_suffix: Option<bool>,
+    pub without_suffix: Option<String>,
+}
+
+/// Returns the content of configuration, `config` is the path given by `--config`, otherwise
+/// `.modeling.toml` in `input` is read if exists. Returns an empty string without configuration.
+pub fn read_config<P: AsRef<Path>>(input: P, config: &str) -> Result<String, Error> {
+    let path = if config.is_empty() {
+        let path = input.as_ref().join(CONFIG_FILE);
+        if !path.is_file() {
+            return Ok("".to_string());
+        }
+        path
+    } else {
+        Path::new(config).to_path_buf()
+    };
+
+    fs::read_to_string(&path)
+        .map_err(|err| format_err!("failed to read config {}: {}", path.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use serde::Deserialize;
+    use tempfile::TempDir;
+
+    use crate::config::{read_config, ModuleConfig, CONFIG_FILE};
+
+    #[derive(Deserialize, Default)]
+    #[serde(default)]
+    struct Config {
+        field_only: bool,
+        modules: HashMap<String, ModuleConfig>,
+    }
+
+    #[test]
+    fn should_read_config_from_input_dir() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!("", read_config(dir.path(), "").unwrap());
+
+        fs::write(dir.path().join(CONFIG_FILE), "field_only = true").unwrap();
+        assert_eq!("field_only = true", read_config(dir.path(), "").unwrap());
+    }
+
+    #[test]
+    fn should_read_given_config() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("modeling.toml");
+        fs::write(&path, "grep = \".*Service\"").unwrap();
+
+        let config = read_config(".", path.to_str().unwrap()).unwrap();
+        assert_eq!("grep = \".*Service\"", config);
+
+        let missing = dir.path().join("missing.toml");
+        assert!(read_config(".", missing.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn should_parse_module_settings() {
+        let config: Config = toml::from_str(
+            r#"
+field_only = true
+
+[modules.api]
+output_type = "mermaid"
+exclude = ["**/test/**"]
+without_parent = true
+
+[modules.legacy]
+skip = true
+"#,
+        )
+        .unwrap();
+
+        assert!(config.field_only);
+        let api = &config.modules["api"];
+        assert_eq!(Some("mermaid".to_string()), api.output_type);
+        assert_eq!(vec!["**/test/**"], api.exclude);
+        assert_eq!(Some(true), api.without_parent);
+        assert_eq!(None, api.field_only);
+        assert!(!api.skip);
+        assert!(config.modules["legacy"].skip);
+    }
+}
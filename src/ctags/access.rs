@@ -0,0 +1,214 @@
+/// Returns the UML visibility marker (`+`, `-`, `#`, `~`) of a member or method.
+///
+/// The `access:` field of ctags is used when present, otherwise the visibility is inferred from
+/// the language's conventions:
+///
+/// * Go: exported names are capitalised, others are package private
+/// * TypeScript / JavaScript: `private`, `protected` and `public` modifiers, `#name` private fields
+/// * Python: `__name` is private, `_name` is protected, dunder methods are public
+/// * Rust: `pub`, `pub(crate)`, `pub(super)` / `pub(in path)`, private by default
+///   (methods of trait impls and enum variants by `rust_visibility_in`)
+pub fn visibility(language: &str, name: &str, pattern: &str, ctags_access: &str) -> String {
+    let source = source_of(pattern);
+    if (language == "TypeScript" || language == "JavaScript") && name.starts_with('#') {
+        return "-".to_string();
+    }
+
+    let marker = from_ctags_access(ctags_access);
+    if !marker.is_empty() {
+        return marker.to_string();
+    }
+
+    let marker = match language {
+        "Go" => go_visibility(name),
+        "TypeScript" | "JavaScript" => type_script_visibility(source),
+        "Python" => python_visibility(name),
+        "Rust" => rust_visibility(source),
+        _ => "",
+    };
+    marker.to_string()
+}
+
+fn from_ctags_access(access: &str) -> &'static str {
+    match access {
+        "public" => "+",
+        "private" => "-",
+        "protected" => "#",
+        "default" | "package" | "internal" => "~",
+        _ => "",
+    }
+}
+
+fn go_visibility(name: &str) -> &'static str {
+    match name.chars().next() {
+        Some(c) if c.is_uppercase() => "+",
+        Some(_) => "~",
+        None => "",
+    }
+}
+
+fn type_script_visibility(source: &str) -> &'static str {
+    for word in source.split_whitespace() {
+        match word {
+            "private" => return "-",
+            "protected" => return "#",
+            "public" => return "+",
+            "static" | "readonly" | "abstract" | "async" | "override" | "declare" => continue,
+            _ => break,
+        }
+    }
+    "+"
+}
+
+fn python_visibility(name: &str) -> &'static str {
+    if name.starts_with("__") && name.ends_with("__") {
+        return "+";
+    }
+
+    if name.starts_with("__") {
+        return "-";
+    }
+
+    if name.starts_with('_') {
+        return "#";
+    }
+
+    "+"
+}
+
+fn rust_visibility(source: &str) -> &'static str {
+    if source.starts_with("pub(crate)") {
+        return "~";
+    }
+
+    if source.starts_with("pub(self)") {
+        return "-";
+    }
+
+    if source.starts_with("pub(") {
+        return "#";
+    }
+
+    if source.starts_with("pub ") {
+        return "+";
+    }
+
+    "-"
+}
+
+/// Returns the visibility of a Rust member or method by the source line of the item it's declared
+/// in, since methods of trait impls and enum variants have no `pub`: methods of trait impls are
+/// public like the trait, variants are as visible as their enum, others keep `access`.
+pub fn rust_visibility_in(item: &str, access: &str) -> String {
+    let words: Vec<&str> = item
+        .split_whitespace()
+        .take_while(|word| !word.starts_with('{'))
+        .collect();
+    let is_impl = match words.as_slice() {
+        ["unsafe", keyword, ..] | [keyword, ..] => keyword.starts_with("impl"),
+        _ => false,
+    };
+    if is_impl && words.contains(&"for") {
+        return "+".to_string();
+    }
+
+    if words.contains(&"enum") {
+        return rust_visibility(item).to_string();
+    }
+    access.to_string()
+}
+
+/// Returns the source line from a ctags pattern, like `/^    pub name: String,$/;"`
+pub(crate) fn source_of(pattern: &str) -> &str {
+    let source = pattern.trim_start_matches("/^");
+    let source = match source.find("$/") {
+        Some(index) => &source[..index],
+        None => source.trim_end_matches("/;\""),
+    };
+    source.trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctags::access::{rust_visibility_in, visibility};
+
+    #[test]
+    fn should_prefer_ctags_access() {
+        assert_eq!("+", visibility("Java", "name", "", "public"));
+        assert_eq!("~", visibility("Java", "name", "", "default"));
+        assert_eq!("~", visibility("C#", "name", "", "internal"));
+    }
+
+    #[test]
+    fn should_infer_go_visibility_by_capitalisation() {
+        assert_eq!("+", visibility("Go", "Name", "/^\tName string$/;\"", ""));
+        assert_eq!("~", visibility("Go", "name", "/^\tname string$/;\"", ""));
+    }
+
+    #[test]
+    fn should_infer_type_script_modifiers() {
+        let pattern = "/^  private readonly name: string$/;\"";
+        assert_eq!("-", visibility("TypeScript", "name", pattern, ""));
+        let pattern = "/^  static protected count: number$/;\"";
+        assert_eq!("#", visibility("TypeScript", "count", pattern, ""));
+        assert_eq!(
+            "+",
+            visibility("TypeScript", "move", "/^  move() {$/;\"", "")
+        );
+        assert_eq!(
+            "-",
+            visibility("TypeScript", "#id", "/^  #id = 1$/;\"", "public")
+        );
+    }
+
+    #[test]
+    fn should_infer_python_underscore_conventions() {
+        assert_eq!("+", visibility("Python", "__init__", "", ""));
+        assert_eq!("-", visibility("Python", "__secret", "", ""));
+        assert_eq!("#", visibility("Python", "_internal", "", ""));
+        assert_eq!("+", visibility("Python", "save", "", ""));
+    }
+
+    #[test]
+    fn should_infer_rust_visibility() {
+        assert_eq!(
+            "+",
+            visibility("Rust", "name", "/^    pub name: String,$/;\"", "")
+        );
+        assert_eq!(
+            "~",
+            visibility("Rust", "name", "/^    pub(crate) name: String,$/;\"", "")
+        );
+        assert_eq!(
+            "#",
+            visibility("Rust", "name", "/^    pub(super) name: String,$/;\"", "")
+        );
+        assert_eq!(
+            "-",
+            visibility("Rust", "name", "/^    name: String,$/;\"", "")
+        );
+        assert_eq!(
+            "+",
+            visibility("Rust", "render", "/^    pub fn render() {$/;\"", "")
+        );
+    }
+
+    #[test]
+    fn should_make_methods_of_rust_trait_impls_public() {
+        assert_eq!("+", rust_visibility_in("impl Default for Order {", "-"));
+        assert_eq!(
+            "+",
+            rust_visibility_in("unsafe impl<T: Send> Send for Queue<T> {", "-")
+        );
+        assert_eq!("-", rust_visibility_in("impl Order {", "-"));
+        assert_eq!("+", rust_visibility_in("impl Order {", "+"));
+    }
+
+    #[test]
+    fn should_give_rust_variants_the_visibility_of_their_enum() {
+        assert_eq!("+", rust_visibility_in("pub enum Kind {", "-"));
+        assert_eq!("~", rust_visibility_in("pub(crate) enum Kind {", "-"));
+        assert_eq!("-", rust_visibility_in("enum Kind {", "-"));
+        assert_eq!("-", rust_visibility_in("pub struct Kind {", "-"));
+    }
+}
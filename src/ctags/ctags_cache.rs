@@ -0,0 +1,230 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use failure::Error;
+use sha2::{Digest, Sha256};
+
+use crate::ctags::ctags_cmd::CtagsVersion;
+use crate::Opt;
+
+/// default cache dir of the command line tools in the input dir
+pub const CACHE_DIR: &str = ".modeling/cache";
+
+/// file in the cache dir with the key of versions and options which the tags depend on
+const VERSION_FILE: &str = "VERSION";
+
+/// Tag lines of ctags for each file, stored under the hash of its path and content, so only
+/// changed files need to be parsed by ctags again.
+pub struct CtagsCache {
+    dir: PathBuf,
+}
+
+impl CtagsCache {
+    /// Opens the cache in `dir`, all entries are removed when `key` changes, see `CtagsCache::key`.
+    pub fn open<P: AsRef<Path>>(dir: P, key: &str) -> Result<CtagsCache, Error> {
+        let dir = dir.as_ref().to_path_buf();
+        let version = dir.join(VERSION_FILE);
+        if fs::read_to_string(&version).ok().as_deref() != Some(key) {
+            if dir.exists() {
+                fs::remove_dir_all(&dir)?;
+            }
+            fs::create_dir_all(&dir)?;
+            fs::write(&version, key)?;
+            // the cache is in the input dir, keep it out of `git ls-files --others`
+            fs::write(dir.join(".gitignore"), "*\n")?;
+        }
+
+        Ok(CtagsCache { dir })
+    }
+
+    /// Returns the key of modeling and ctags versions with the options passed to ctags.
+    pub fn key(opt: &Opt, version: &CtagsVersion) -> String {
+        format!(
+            "modeling {}\n{}\nfields={:?} languages={:?} exclude={:?} unsorted={} opt_ctags={:?}\n",
+            env!("CARGO_PKG_VERSION"),
+            version.version,
+            opt.fields,
+            opt.languages,
+            opt.exclude,
+            opt.unsorted,
+            opt.opt_ctags
+        )
+    }
+
+    /// Returns the hash of the file in `dir`, `None` if it can't be read.
+    pub fn hash<P: AsRef<Path>>(dir: P, file: &str) -> Option<String> {
+        let content = fs::read(dir.as_ref().join(file)).ok()?;
+        let mut hasher = Sha256::new();
+        hasher.update(file.as_bytes());
+        hasher.update([0]);
+        hasher.update(&content);
+        Some(format!("{:x}", hasher.finalize()))
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.entry(hash).is_file()
+    }
+
+    pub fn get(&self, hash: &str) -> Option<Vec<String>> {
+        let content = fs::read_to_string(self.entry(hash)).ok()?;
+        Some(content.lines().map(|line| line.to_string()).collect())
+    }
+
+    pub fn put(&self, hash: &str, lines: &[String]) -> Result<(), Error> {
+        let entry = self.entry(hash);
+        if let Some(parent) = entry.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(entry, lines.join("\n"))?;
+        Ok(())
+    }
+
+    /// Removes entries except the ones of `hashes`, which are the files of the current run, so
+    /// entries of changed or deleted files don't pile up.
+    pub fn retain(&self, hashes: &HashSet<&str>) -> Result<(), Error> {
+        for sub_dir in fs::read_dir(&self.dir)?.flatten() {
+            if !sub_dir.file_type()?.is_dir() {
+                continue;
+            }
+
+            let prefix = sub_dir.file_name().to_string_lossy().to_string();
+            for entry in fs::read_dir(sub_dir.path())?.flatten() {
+                let hash = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+                if !hashes.contains(hash.as_str()) {
+                    fs::remove_file(entry.path())?;
+                }
+            }
+            // fails if some entries are kept
+            let _ = fs::remove_dir(sub_dir.path());
+        }
+        Ok(())
+    }
+
+    /// entries are split into sub dirs by the first 2 chars of hash
+    fn entry(&self, hash: &str) -> PathBuf {
+        let (prefix, rest) = hash.split_at(2.min(hash.len()));
+        self.dir.join(prefix).join(rest)
+    }
+}
+
+/// Returns the file of a tag line, the second field of ctags output, `None` for pseudo tags.
+pub fn file_of(line: &str) -> Option<&str> {
+    if line.starts_with("!_") {
+        return None;
+    }
+    line.split('\t').nth(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use structopt::StructOpt;
+    use tempfile::TempDir;
+
+    use crate::ctags::ctags_cache::{file_of, CtagsCache};
+    use crate::ctags::ctags_cmd::CtagsVersion;
+    use crate::Opt;
+
+    fn lines(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn should_store_lines_by_hash() {
+        let dir = TempDir::new().unwrap();
+        let cache = CtagsCache::open(dir.path().join("cache"), "v1").unwrap();
+        let hash = "ab12cd";
+
+        assert_eq!(None, cache.get(hash));
+        assert!(!cache.contains(hash));
+        cache
+            .put(hash, &lines(&["A\ta.rs\t/^struct A;$/;\"\tkind:struct"]))
+            .unwrap();
+        assert!(cache.contains(hash));
+        assert_eq!(
+            Some(lines(&["A\ta.rs\t/^struct A;$/;\"\tkind:struct"])),
+            cache.get(hash)
+        );
+
+        cache.put("ef34", &[]).unwrap();
+        assert_eq!(Some(vec![]), cache.get("ef34"));
+    }
+
+    #[test]
+    fn should_remove_entries_not_retained() {
+        let dir = TempDir::new().unwrap();
+        let cache_dir = dir.path().join("cache");
+        let cache = CtagsCache::open(&cache_dir, "v1").unwrap();
+        cache.put("ab12", &lines(&["A"])).unwrap();
+        cache.put("ab34", &lines(&["B"])).unwrap();
+        cache.put("cd56", &lines(&["C"])).unwrap();
+
+        cache.retain(&["ab12"].iter().cloned().collect()).unwrap();
+
+        assert!(cache.contains("ab12"));
+        assert!(!cache.contains("ab34"));
+        assert!(!cache_dir.join("cd").exists());
+        assert!(CtagsCache::open(&cache_dir, "v1")
+            .unwrap()
+            .get("ab12")
+            .is_some());
+    }
+
+    #[test]
+    fn should_clear_entries_when_key_changed() {
+        let dir = TempDir::new().unwrap();
+        let cache_dir = dir.path().join("cache");
+        CtagsCache::open(&cache_dir, "v1")
+            .unwrap()
+            .put("ab12", &lines(&["A"]))
+            .unwrap();
+
+        assert!(CtagsCache::open(&cache_dir, "v1")
+            .unwrap()
+            .get("ab12")
+            .is_some());
+        assert!(CtagsCache::open(&cache_dir, "v2")
+            .unwrap()
+            .get("ab12")
+            .is_none());
+    }
+
+    #[test]
+    fn should_hash_by_path_and_content() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.rs"), "struct A;").unwrap();
+        fs::write(dir.path().join("b.rs"), "struct A;").unwrap();
+
+        let hash = CtagsCache::hash(dir.path(), "a.rs").unwrap();
+        assert_eq!(64, hash.len());
+        assert_ne!(hash, CtagsCache::hash(dir.path(), "b.rs").unwrap());
+
+        fs::write(dir.path().join("a.rs"), "struct B;").unwrap();
+        assert_ne!(hash, CtagsCache::hash(dir.path(), "a.rs").unwrap());
+        assert_eq!(None, CtagsCache::hash(dir.path(), "c.rs"));
+    }
+
+    #[test]
+    fn should_change_key_with_options() {
+        let version = CtagsVersion::parse("Universal Ctags 5.9.0").unwrap();
+        let opt = Opt::from_iter(["ptags", "--fields=+latinK"].iter());
+        let other = Opt::from_iter(["ptags", "--fields=+latinK", "--languages=Rust"].iter());
+
+        assert_ne!(
+            CtagsCache::key(&opt, &version),
+            CtagsCache::key(&other, &version)
+        );
+    }
+
+    #[test]
+    fn should_find_file_of_tag_lines() {
+        assert_eq!(None, file_of("!_TAG_FILE_FORMAT\t2\t/extended format/"));
+        assert_eq!(
+            Some("src/a.rs"),
+            file_of("A\tsrc/a.rs\t/^struct A;$/;\"\tkind:struct")
+        );
+        assert_eq!(None, file_of("no tabs"));
+    }
+}
@@ -20,16 +20,19 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 // https://github.com/dalance/ptags
+use std::collections::VecDeque;
 use std::fs;
 use std::fs::File;
-use std::io::{BufReader, Read, Write};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
 #[cfg(target_os = "linux")]
 use std::os::unix::io::AsRawFd;
 use std::path::PathBuf;
-use std::process::{ChildStdin, Command, Output, Stdio};
+use std::process::{Child, ChildStdin, Command, Output, Stdio};
 use std::str;
-use std::sync::mpsc;
-use std::thread;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 use failure::{bail, Error, Fail, ResultExt};
 #[cfg(target_os = "linux")]
@@ -51,38 +54,136 @@ enum CtagsError {
 
     #[fail(display = "failed to convert to UTF-8 ({:?})", s)]
     ConvFailed { s: Vec<u8> },
+
+    #[fail(
+        display = "ctags command ({}) is not found, please install Universal Ctags (https://ctags.io) or check the path of ctags",
+        bin
+    )]
+    NotFound { bin: String },
+
+    #[fail(
+        display = "unsupported ctags command ({}), please install Universal Ctags (https://ctags.io)\n{}",
+        bin, version
+    )]
+    Unsupported { bin: String, version: String },
+
+    #[fail(
+        display = "ctags option ({}) is only supported by Universal Ctags (https://ctags.io), but the ctags is {}",
+        option, version
+    )]
+    UnsupportedOption { option: String, version: String },
 }
 
 // ---------------------------------------------------------------------------------------------------------------------
-// CmdCtags
+// CtagsVersion
 // ---------------------------------------------------------------------------------------------------------------------
-pub struct CmdCtags;
+/// fields of `--fields` supported by Exuberant Ctags
+const EXUBERANT_FIELDS: &str = "afikKlmnsSzt";
+
+/// prefixes of options only supported by Universal Ctags, like `--kinds-Java=+l` of languages in
+/// the config, Exuberant Ctags names it `--Java-kinds`
+const UNIVERSAL_OPTIONS: [&str; 9] = [
+    "--kinds-",
+    "--kinddef-",
+    "--fields-",
+    "--extras",
+    "--map-",
+    "--param-",
+    "--roles-",
+    "--output-format",
+    "--pattern-length-limit",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CtagsKind {
+    Universal,
+    Exuberant,
+}
 
-impl CmdCtags {
-    pub fn call(opt: &Opt, files: &[String]) -> Result<Vec<Output>, Error> {
-        let mut args = Vec::new();
-        args.push(String::from("-L -"));
-        args.push(String::from("-f -"));
-        if opt.unsorted {
-            args.push(String::from("--sort=no"));
-        }
-        if opt.fields.is_some() {
-            args.push(String::from(format!(
-                "--fields={}",
-                opt.fields.as_ref().unwrap()
-            )));
-        }
-        for e in &opt.exclude {
-            args.push(String::from(format!("--exclude={}", e)));
+#[derive(Debug, Clone, PartialEq)]
+pub struct CtagsVersion {
+    pub kind: CtagsKind,
+    /// first line of `ctags --version`
+    pub version: String,
+}
+
+impl CtagsVersion {
+    /// Parses the output of `ctags --version`, returns `None` for others like BSD ctags.
+    pub fn parse(text: &str) -> Option<CtagsVersion> {
+        let version = text.lines().next()?.trim().to_string();
+        let kind = if version.starts_with("Universal Ctags") {
+            CtagsKind::Universal
+        } else if version.starts_with("Exuberant Ctags") {
+            CtagsKind::Exuberant
+        } else {
+            return None;
+        };
+
+        Some(CtagsVersion { kind, version })
+    }
+
+    /// Removes fields which the ctags doesn't support, like `+latinKZ` to `+latinK` for
+    /// Exuberant Ctags, the default `+latinK` is supported by both.
+    pub fn fields(&self, fields: &str) -> String {
+        match self.kind {
+            CtagsKind::Universal => fields.to_string(),
+            CtagsKind::Exuberant => {
+                let mut result = String::new();
+                let mut sign = None;
+                for c in fields.chars() {
+                    if c == '+' || c == '-' {
+                        sign = Some(c);
+                    } else if EXUBERANT_FIELDS.contains(c) {
+                        // a sign is kept only with the supported fields after it
+                        if let Some(sign) = sign.take() {
+                            result.push(sign);
+                        }
+                        result.push(c);
+                    }
+                }
+                result
+            }
         }
+    }
 
-        if opt.languages.is_some() {
-            let langs = opt.languages.as_ref().unwrap();
-            args.push(String::from(format!("--languages={}", langs)));
+    /// Returns the first of `options` which the ctags doesn't support, only Exuberant Ctags lacks
+    /// some options.
+    pub fn unsupported_option<'a>(&self, options: &'a [String]) -> Option<&'a String> {
+        match self.kind {
+            CtagsKind::Universal => None,
+            CtagsKind::Exuberant => options.iter().find(|option| {
+                UNIVERSAL_OPTIONS
+                    .iter()
+                    .any(|prefix| option.starts_with(prefix))
+            }),
         }
+    }
+}
 
-        args.append(&mut opt.opt_ctags.clone());
+/// Outputs of ctags processes in `CmdCtags::stream`.
+#[derive(Debug)]
+pub struct StreamOutput {
+    /// stdout is empty, stderr is kept for `CmdCtags::warnings`
+    pub outputs: Vec<Output>,
+    /// files of batches killed after `Opt::timeout`, their tags may be incomplete
+    pub timed_out: Vec<String>,
+}
+
+/// A message of ctags in stderr, `file` is empty if the message is not about an input file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CtagsWarning {
+    pub file: String,
+    pub message: String,
+}
 
+// ---------------------------------------------------------------------------------------------------------------------
+// CmdCtags
+// ---------------------------------------------------------------------------------------------------------------------
+pub struct CmdCtags;
+
+impl CmdCtags {
+    pub fn call(opt: &Opt, files: &[String]) -> Result<Vec<Output>, Error> {
+        let args = CmdCtags::args(opt, &CmdCtags::version(opt)?)?;
         let cmd = CmdCtags::get_cmd(&opt, &args);
 
         let (tx, rx) = mpsc::channel::<Result<Output, Error>>();
@@ -105,8 +206,7 @@ impl CmdCtags {
                     .current_dir(dir)
                     .stdin(Stdio::piped())
                     .stdout(Stdio::piped())
-                    //.stderr(Stdio::piped()) // Stdio::piped is x2 slow to wait_with_output() completion
-                    .stderr(Stdio::null())
+                    .stderr(Stdio::piped())
                     .spawn();
                 match child {
                     Ok(mut x) => {
@@ -140,23 +240,131 @@ impl CmdCtags {
 
         let mut outputs = Vec::new();
         for child in children {
-            let output = child??;
-
-            if !output.status.success() {
-                bail!(CtagsError::ExecFailed {
-                    cmd,
-                    err: String::from(str::from_utf8(&output.stderr).context(
-                        CtagsError::ConvFailed {
-                            s: output.stderr.to_vec(),
-                        }
-                    )?)
-                });
+            outputs.push(CmdCtags::check_status(&cmd, child??)?);
+        }
+
+        Ok(outputs)
+    }
+
+    /// Calls ctags like `call`, but passes each line of stdout to `on_line` while ctags is
+    /// running. `files` are batches of file lists, `opt.thread` workers take them from a queue,
+    /// so a batch of large files doesn't hold back the others. Files of batches which timed out
+    /// are returned with the outputs. `version` is probed by `version` before.
+    pub fn stream<F: FnMut(&str)>(
+        opt: &Opt,
+        version: &CtagsVersion,
+        files: &[String],
+        mut on_line: F,
+    ) -> Result<StreamOutput, Error> {
+        let args = CmdCtags::args(opt, version)?;
+        let cmd = CmdCtags::get_cmd(&opt, &args);
+
+        let queue: VecDeque<String> = files.iter().cloned().collect();
+        let queue = Arc::new(Mutex::new(queue));
+        let (tx, rx) = mpsc::sync_channel::<Streamed>(STREAM_BOUND);
+
+        for _ in 0..opt.thread.min(files.len()) {
+            let tx = tx.clone();
+            let queue = queue.clone();
+            let batch = Batch {
+                bin_ctags: opt.bin_ctags.clone(),
+                args: args.clone(),
+                dir: opt.dir.clone(),
+                cmd: cmd.clone(),
+                timeout: opt.timeout,
+            };
+
+            if opt.verbose {
+                eprintln!("Call : {}", cmd);
             }
 
-            outputs.push(output);
+            thread::spawn(move || loop {
+                let file = match queue.lock().unwrap().pop_front() {
+                    Some(file) => file,
+                    None => break,
+                };
+                if !batch.run(file, &tx) {
+                    break;
+                }
+            });
         }
+        drop(tx);
 
-        Ok(outputs)
+        let mut outputs = Vec::new();
+        let mut timed_out = Vec::new();
+        for streamed in rx {
+            match streamed {
+                Streamed::Line(line) => {
+                    if opt.validate_utf8 {
+                        on_line(
+                            str::from_utf8(&line)
+                                .context(CtagsError::ConvFailed { s: line.clone() })?,
+                        );
+                    } else {
+                        on_line(&String::from_utf8_lossy(&line));
+                    }
+                }
+                Streamed::Exited(output) => {
+                    outputs.push(CmdCtags::check_status(&cmd, output?)?);
+                }
+                Streamed::TimedOut(output, files) => {
+                    outputs.push(output);
+                    timed_out.extend(files.lines().map(|file| file.to_string()));
+                }
+            }
+        }
+
+        Ok(StreamOutput { outputs, timed_out })
+    }
+
+    /// Returns the arguments of ctags, `--fields` are adjusted to the version of ctags, and
+    /// options of `opt_ctags` which it doesn't support are an error.
+    fn args(opt: &Opt, version: &CtagsVersion) -> Result<Vec<String>, Error> {
+        let mut args = Vec::new();
+        args.push(String::from("-L -"));
+        args.push(String::from("-f -"));
+        if opt.unsorted {
+            args.push(String::from("--sort=no"));
+        }
+        if opt.fields.is_some() {
+            args.push(String::from(format!(
+                "--fields={}",
+                version.fields(opt.fields.as_ref().unwrap())
+            )));
+        }
+        for e in &opt.exclude {
+            args.push(String::from(format!("--exclude={}", e)));
+        }
+
+        if opt.languages.is_some() {
+            let langs = opt.languages.as_ref().unwrap();
+            args.push(String::from(format!("--languages={}", langs)));
+        }
+
+        if let Some(option) = version.unsupported_option(&opt.opt_ctags) {
+            bail!(CtagsError::UnsupportedOption {
+                option: option.to_string(),
+                version: version.version.clone(),
+            });
+        }
+        args.append(&mut opt.opt_ctags.clone());
+
+        Ok(args)
+    }
+
+    fn check_status(cmd: &str, output: Output) -> Result<Output, Error> {
+        if !output.status.success() {
+            bail!(CtagsError::ExecFailed {
+                cmd: cmd.to_string(),
+                err: String::from(str::from_utf8(&output.stderr).context(
+                    CtagsError::ConvFailed {
+                        s: output.stderr.to_vec(),
+                    }
+                )?)
+            });
+        }
+
+        Ok(output)
     }
 
     pub fn get_tags_header(opt: &Opt) -> Result<String, Error> {
@@ -194,13 +402,40 @@ impl CmdCtags {
         cmd
     }
 
-    #[allow(dead_code)]
-    fn is_exuberant_ctags(opt: &Opt) -> Result<bool, Error> {
-        let output = Command::new(&opt.bin_ctags)
+    /// Probes `opt.bin_ctags` by `--version`, fails if it's not found or is not Universal or
+    /// Exuberant Ctags.
+    pub fn version(opt: &Opt) -> Result<CtagsVersion, Error> {
+        let bin = opt.bin_ctags.to_string_lossy().to_string();
+        let output = match Command::new(&opt.bin_ctags)
             .arg("--version")
             .current_dir(&opt.dir)
-            .output()?;
-        Ok(str::from_utf8(&output.stdout)?.starts_with("Exuberant Ctags"))
+            .output()
+        {
+            Ok(output) => output,
+            Err(err) if err.kind() == ErrorKind::NotFound => bail!(CtagsError::NotFound { bin }),
+            Err(err) => bail!(CtagsError::ExecFailed {
+                cmd: format!("{} --version", bin),
+                err: err.to_string()
+            }),
+        };
+
+        let text = String::from_utf8_lossy(&output.stdout);
+        match CtagsVersion::parse(&text) {
+            Some(version) => Ok(version),
+            None => bail!(CtagsError::Unsupported {
+                bin,
+                version: format!("{}{}", text, String::from_utf8_lossy(&output.stderr)),
+            }),
+        }
+    }
+
+    /// Returns messages in stderr of `outputs`, `files` are the file lists passed to `call`.
+    pub fn warnings(outputs: &[Output], files: &[String]) -> Vec<CtagsWarning> {
+        let files: Vec<&str> = files.iter().flat_map(|list| list.lines()).collect();
+        outputs
+            .iter()
+            .flat_map(|output| parse_warnings(&String::from_utf8_lossy(&output.stderr), &files))
+            .collect()
     }
 
     #[cfg(target_os = "linux")]
@@ -215,6 +450,172 @@ impl CmdCtags {
     }
 }
 
+// ---------------------------------------------------------------------------------------------------------------------
+// Batch
+// ---------------------------------------------------------------------------------------------------------------------
+/// lines buffered between ctags processes and the parser in `CmdCtags::stream`
+const STREAM_BOUND: usize = 4096;
+
+enum Streamed {
+    Line(Vec<u8>),
+    Exited(Result<Output, Error>),
+    /// killed after `Opt::timeout` with the file list of the batch, stderr tells the files too
+    TimedOut(Output, String),
+}
+
+/// A ctags process of `CmdCtags::stream` for a list of files.
+struct Batch {
+    bin_ctags: PathBuf,
+    args: Vec<String>,
+    dir: PathBuf,
+    cmd: String,
+    timeout: u64,
+}
+
+impl Batch {
+    /// Sends lines of stdout, then how the process exited, returns false if the receiver is gone.
+    fn run(&self, file: String, tx: &SyncSender<Streamed>) -> bool {
+        let child = Command::new(&self.bin_ctags)
+            .args(&self.args)
+            .current_dir(&self.dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut x = match child {
+            Ok(x) => x,
+            Err(_) => {
+                let cmd = self.cmd.clone();
+                return tx
+                    .send(Streamed::Exited(Err(CtagsError::CallFailed { cmd }.into())))
+                    .is_ok();
+            }
+        };
+
+        // stdin and stderr have their own threads, so ctags is never blocked by a full pipe
+        let mut stdin = x.stdin.take().unwrap();
+        let list = file.clone();
+        let writer = thread::spawn(move || {
+            let _ = stdin.write_all(list.as_bytes());
+        });
+        let mut stderr = x.stderr.take().unwrap();
+        let reader = thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = stderr.read_to_end(&mut buf);
+            buf
+        });
+        let mut stdout = BufReader::new(x.stdout.take().unwrap());
+
+        let child = Arc::new(Mutex::new(x));
+        let (done, watchdog) = self.watch(&child);
+
+        let mut sent = true;
+        loop {
+            let mut line = Vec::new();
+            match stdout.read_until(b'\n', &mut line) {
+                Ok(0) => break,
+                Ok(_) => {
+                    while line.ends_with(b"\n") || line.ends_with(b"\r") {
+                        line.pop();
+                    }
+                    // the receiver is gone after an error of another batch
+                    if tx.send(Streamed::Line(line)).is_err() {
+                        let _ = child.lock().unwrap().kill();
+                        sent = false;
+                        break;
+                    }
+                }
+                Err(err) => {
+                    let _ = child.lock().unwrap().kill();
+                    sent = tx.send(Streamed::Exited(Err(err.into()))).is_ok();
+                    break;
+                }
+            }
+        }
+
+        // ctags may close stdout before it exits, so the watchdog is kept until it exits, and
+        // the child is never locked while it runs
+        let status = loop {
+            match child.lock().unwrap().try_wait() {
+                Ok(Some(status)) => break Ok(status),
+                Ok(None) => {}
+                Err(err) => break Err(err),
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+        drop(done);
+        let timed_out = watchdog.is_some_and(|watchdog| watchdog.join().unwrap_or(false));
+        let _ = writer.join();
+        let mut stderr = reader.join().unwrap_or_default();
+        let status = match status {
+            Ok(status) => status,
+            Err(err) => return tx.send(Streamed::Exited(Err(err.into()))).is_ok(),
+        };
+        if !sent {
+            return false;
+        }
+
+        if timed_out {
+            for file in file.lines() {
+                let message = format!(
+                    "ctags: timed out after {}s, tags may be incomplete: {}\n",
+                    self.timeout, file
+                );
+                stderr.extend(message.as_bytes());
+            }
+        }
+        let output = Output {
+            status,
+            stdout: Vec::new(),
+            stderr,
+        };
+        let streamed = if timed_out {
+            Streamed::TimedOut(output, file)
+        } else {
+            Streamed::Exited(Ok(output))
+        };
+        tx.send(streamed).is_ok()
+    }
+
+    /// Kills the child unless `done` is dropped within `timeout`, the watchdog returns whether
+    /// it's killed.
+    fn watch(&self, child: &Arc<Mutex<Child>>) -> (Sender<()>, Option<JoinHandle<bool>>) {
+        let (done, finished) = mpsc::channel::<()>();
+        if self.timeout == 0 {
+            return (done, None);
+        }
+
+        let child = child.clone();
+        let timeout = Duration::from_secs(self.timeout);
+        let watchdog = thread::spawn(move || match finished.recv_timeout(timeout) {
+            Err(RecvTimeoutError::Timeout) => child.lock().unwrap().kill().is_ok(),
+            _ => false,
+        });
+        (done, Some(watchdog))
+    }
+}
+
+/// Attributes each line to the longest input file it mentions, like
+/// `ctags: Warning: cannot open input file "src/a.rs" : No such file or directory`
+fn parse_warnings(stderr: &str, files: &[&str]) -> Vec<CtagsWarning> {
+    stderr
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let file = files
+                .iter()
+                .filter(|file| !file.is_empty() && line.contains(*file))
+                .max_by_key(|file| file.len())
+                .unwrap_or(&"");
+            let message = line.trim_start_matches("ctags: ").trim().to_string();
+            CtagsWarning {
+                file: file.to_string(),
+                message,
+            }
+        })
+        .collect()
+}
+
 // ---------------------------------------------------------------------------------------------------------------------
 // Test
 // ---------------------------------------------------------------------------------------------------------------------
@@ -228,7 +629,7 @@ mod tests {
 
     use crate::Opt;
 
-    use super::CmdCtags;
+    use super::{parse_warnings, CmdCtags, CtagsKind, CtagsVersion, CtagsWarning};
 
     #[test]
     fn test_call() {
@@ -327,4 +728,237 @@ mod tests {
         println!("{:}", out_str);
         // assert!(lines.next().is_none())
     }
+
+    #[test]
+    fn should_parse_ctags_version() {
+        let universal =
+            CtagsVersion::parse("Universal Ctags 5.9.0, Copyright (C) 2015 Universal Ctags Team\n")
+                .unwrap();
+        assert_eq!(CtagsKind::Universal, universal.kind);
+        assert_eq!(
+            "Universal Ctags 5.9.0, Copyright (C) 2015 Universal Ctags Team",
+            universal.version
+        );
+
+        let exuberant =
+            CtagsVersion::parse("Exuberant Ctags 5.8, Copyright (C) 1996-2009 Darren Hiebert")
+                .unwrap();
+        assert_eq!(CtagsKind::Exuberant, exuberant.kind);
+
+        assert_eq!(
+            None,
+            CtagsVersion::parse("usage: ctags [-BFadtuwvx] [-f tagsfile] file ...")
+        );
+    }
+
+    #[test]
+    fn should_remove_fields_not_supported_by_exuberant() {
+        let exuberant = CtagsVersion::parse("Exuberant Ctags 5.8").unwrap();
+        assert_eq!("+latinK", exuberant.fields("+latinKZ"));
+        assert_eq!("+latinK", exuberant.fields("+latinK-E"));
+        assert_eq!("+latinK-s", exuberant.fields("+latinKZ-Es"));
+
+        let universal = CtagsVersion::parse("Universal Ctags 5.9.0").unwrap();
+        assert_eq!("+latinKZ", universal.fields("+latinKZ"));
+    }
+
+    #[test]
+    fn should_reject_options_not_supported_by_exuberant() {
+        let mut opt = Opt::from_iter(["ptags"].iter());
+        opt.opt_ctags = vec![
+            "--regex-Java=/^record ([A-Za-z]+)/\\1/c/".to_string(),
+            "--kinds-Java=+l".to_string(),
+        ];
+
+        let exuberant = CtagsVersion::parse("Exuberant Ctags 5.8").unwrap();
+        let err = CmdCtags::args(&opt, &exuberant).unwrap_err().to_string();
+        assert!(err.contains("(--kinds-Java=+l)"), "{}", err);
+        assert!(err.contains("Exuberant Ctags 5.8"), "{}", err);
+
+        let universal = CtagsVersion::parse("Universal Ctags 5.9.0").unwrap();
+        let args = CmdCtags::args(&opt, &universal).unwrap();
+        assert!(args.contains(&"--kinds-Java=+l".to_string()));
+
+        opt.opt_ctags.pop();
+        assert!(CmdCtags::args(&opt, &exuberant).is_ok());
+    }
+
+    #[test]
+    fn should_report_missing_ctags() {
+        let args = ["ptags", "--bin-ctags", "modeling-missing-ctags"];
+        let opt = Opt::from_iter(args.iter());
+
+        let err = CmdCtags::version(&opt).unwrap_err().to_string();
+
+        assert!(err.contains("(modeling-missing-ctags) is not found"));
+        assert!(CmdCtags::call(&opt, &[String::new()]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_probe_ctags_by_version() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let bin = dir.path().join("ctags");
+        std::fs::write(
+            &bin,
+            "#!/bin/sh\necho 'Exuberant Ctags 5.8, Copyright (C) 1996-2009'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let mut opt = Opt::from_iter(["ptags"].iter());
+        opt.bin_ctags = bin.clone();
+
+        assert_eq!(CtagsKind::Exuberant, CmdCtags::version(&opt).unwrap().kind);
+
+        std::fs::write(
+            &bin,
+            "#!/bin/sh\necho 'ctags: illegal option -- -' >&2\nexit 1\n",
+        )
+        .unwrap();
+        let err = CmdCtags::version(&opt).unwrap_err().to_string();
+        assert!(err.contains("unsupported ctags command"));
+        assert!(err.contains("illegal option"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn should_stream_lines_of_all_threads() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let bin = dir.path().join("ctags");
+        // prints a tag for each input file, and a warning for the file `c.rs`
+        std::fs::write(
+            &bin,
+            "#!/bin/sh\n\
+             if [ \"$1\" = \"--version\" ]; then echo 'Universal Ctags 5.9.0'; exit 0; fi\n\
+             while read f; do\n\
+               if [ \"$f\" = c.rs ]; then echo \"ctags: Warning: cannot open input file \\\"$f\\\"\" >&2; fi\n\
+               printf 'main\\t%s\\t/^fn main() {$/;\"\\tfunction\\r\\n' \"$f\"\n\
+             done\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let mut opt = Opt::from_iter(["ptags", "-t", "2"].iter());
+        opt.bin_ctags = bin;
+        let files = vec!["a.rs\nc.rs\n".to_string(), "b.rs\n".to_string()];
+
+        let version = CmdCtags::version(&opt).unwrap();
+        let mut lines = vec![];
+        let streamed =
+            CmdCtags::stream(&opt, &version, &files, |line| lines.push(line.to_string())).unwrap();
+        let outputs = streamed.outputs;
+        lines.sort();
+
+        assert_eq!(
+            vec![
+                "main\ta.rs\t/^fn main() {$/;\"\tfunction",
+                "main\tb.rs\t/^fn main() {$/;\"\tfunction",
+                "main\tc.rs\t/^fn main() {$/;\"\tfunction",
+            ],
+            lines
+        );
+        assert!(outputs.iter().all(|output| output.stdout.is_empty()));
+        let warnings = CmdCtags::warnings(&outputs, &files);
+        assert_eq!(1, warnings.len());
+        assert_eq!("c.rs", warnings[0].file);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn should_kill_ctags_after_timeout() {
+        use std::os::unix::fs::PermissionsExt;
+        use std::time::{Duration, Instant};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let bin = dir.path().join("ctags");
+        // hangs on the file `slow.rs`
+        std::fs::write(
+            &bin,
+            "#!/bin/sh\n\
+             if [ \"$1\" = \"--version\" ]; then echo 'Universal Ctags 5.9.0'; exit 0; fi\n\
+             while read f; do\n\
+               if [ \"$f\" = slow.rs ]; then exec sleep 30; fi\n\
+               printf 'main\\t%s\\t/^fn main() {$/;\"\\tfunction\\n' \"$f\"\n\
+             done\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let mut opt = Opt::from_iter(["ptags", "-t", "2", "--timeout", "1"].iter());
+        opt.bin_ctags = bin;
+        let files = ["slow.rs\n".to_string(), "a.rs\n".to_string()];
+
+        let start = Instant::now();
+        let version = CmdCtags::version(&opt).unwrap();
+        let mut lines = vec![];
+        let streamed =
+            CmdCtags::stream(&opt, &version, &files, |line| lines.push(line.to_string())).unwrap();
+        let outputs = streamed.outputs;
+
+        assert!(start.elapsed() < Duration::from_secs(20));
+        assert_eq!(vec!["slow.rs"], streamed.timed_out);
+        assert_eq!(vec!["main\ta.rs\t/^fn main() {$/;\"\tfunction"], lines);
+        let warnings = CmdCtags::warnings(&outputs, &files);
+        assert_eq!(1, warnings.len());
+        assert_eq!("slow.rs", warnings[0].file);
+        assert!(warnings[0].message.starts_with("timed out after 1s"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn should_kill_ctags_which_closed_stdout_after_timeout() {
+        use std::os::unix::fs::PermissionsExt;
+        use std::time::{Duration, Instant};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let bin = dir.path().join("ctags");
+        // closes stdout and hangs on the file `slow.rs`
+        std::fs::write(
+            &bin,
+            "#!/bin/sh\n\
+             if [ \"$1\" = \"--version\" ]; then echo 'Universal Ctags 5.9.0'; exit 0; fi\n\
+             while read f; do\n\
+               if [ \"$f\" = slow.rs ]; then exec sleep 30 >&-; fi\n\
+             done\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let mut opt = Opt::from_iter(["ptags", "--timeout", "1"].iter());
+        opt.bin_ctags = bin;
+        let files = ["slow.rs\n".to_string()];
+
+        let start = Instant::now();
+        let version = CmdCtags::version(&opt).unwrap();
+        let streamed = CmdCtags::stream(&opt, &version, &files, |_| {}).unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(20));
+        assert_eq!(vec!["slow.rs"], streamed.timed_out);
+    }
+
+    #[test]
+    fn should_attribute_warnings_to_files() {
+        let stderr =
+            "ctags: Warning: cannot open input file \"src/a.rs\" : No such file or directory\n\
+                      ctags: Warning: src/ab.rs:12: null expansion of name pattern \"\\1\"\n\
+                      ctags: Warning: Unsupported parameter 'x' for \"--fields\" option\n";
+
+        let warnings = parse_warnings(stderr, &["src/a.rs", "src/ab.rs"]);
+
+        assert_eq!(3, warnings.len());
+        assert_eq!("src/a.rs", warnings[0].file);
+        assert_eq!(
+            "Warning: cannot open input file \"src/a.rs\" : No such file or directory",
+            warnings[0].message
+        );
+        assert_eq!("src/ab.rs", warnings[1].file);
+        assert_eq!(
+            CtagsWarning {
+                file: "".to_string(),
+                message: "Warning: Unsupported parameter 'x' for \"--fields\" option".to_string()
+            },
+            warnings[2]
+        );
+    }
 }
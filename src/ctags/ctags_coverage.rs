@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+/// samples of tag lines kept for each reason, language and kind
+pub const SAMPLES: usize = 3;
+
+/// Why a tag line is missing in the model, or only partly in it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Dropped {
+    /// not a tag line of the extended format
+    Unparsed,
+    /// a class whose name is not a word, like `Map<K, V>`
+    ClassName,
+    /// a kind which is not a word, a name which is not a word or no `line:` field
+    NoLine,
+    /// not in the scope of a class, like top level functions or interfaces
+    NoScope,
+    /// in the scope of a class, but its kind is neither a member nor a method
+    UnknownKind,
+    /// in the scope of a class which is never tagged
+    Orphan,
+    /// kept as a member or method, but its data type is not found in the source line
+    NoDataType,
+}
+
+impl Dropped {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dropped::Unparsed => "unparsed",
+            Dropped::ClassName => "class name",
+            Dropped::NoLine => "no line",
+            Dropped::NoScope => "no scope",
+            Dropped::UnknownKind => "unknown kind",
+            Dropped::Orphan => "orphan",
+            Dropped::NoDataType => "no data type",
+        }
+    }
+
+    /// tags without data type are still in the model
+    pub fn is_kept(&self) -> bool {
+        *self == Dropped::NoDataType
+    }
+}
+
+/// Tag lines dropped for the same reason, language and kind.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DroppedTags {
+    pub reason: Dropped,
+    pub language: String,
+    pub kind: String,
+    pub count: usize,
+    /// first tag lines, at most `SAMPLES`
+    pub samples: Vec<String>,
+}
+
+/// Report of the tag lines parsed into the model, with the ones dropped by the parser.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Coverage {
+    /// tag lines of ctags, without pseudo tags like `!_TAG_FILE_SORTED`
+    pub lines: usize,
+    /// tag lines in the model, including the ones without data type
+    pub parsed: usize,
+    /// by reason, then by count from the most
+    pub dropped: Vec<DroppedTags>,
+}
+
+impl Coverage {
+    /// Returns a report for humans, like:
+    ///
+    /// ```text
+    /// coverage: 1180 of 1200 tag lines parsed (98.3%)
+    ///   no scope      Java       interface        1  Repository  Repository.java  ...
+    /// ```
+    pub fn summary(&self) -> String {
+        let percent = match self.lines {
+            0 => 100.0,
+            lines => self.parsed as f64 * 100.0 / lines as f64,
+        };
+        let mut summary = format!(
+            "coverage: {} of {} tag lines parsed ({:.1}%)",
+            self.parsed, self.lines, percent
+        );
+
+        for tags in &self.dropped {
+            let _ = write!(
+                summary,
+                "\n  {:<13} {:<10} {:<12} {:>6}",
+                tags.reason.name(),
+                tags.language,
+                tags.kind,
+                tags.count
+            );
+            if let Some(sample) = tags.samples.first() {
+                let _ = write!(summary, "  {}", sample);
+            }
+        }
+
+        summary
+    }
+}
+
+/// Counts tag lines dropped by the parser by reason, language and kind, with a few samples.
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    lines: usize,
+    dropped: HashMap<(Dropped, String, String), DroppedTags>,
+}
+
+impl Diagnostics {
+    pub fn count_line(&mut self) {
+        self.lines += 1;
+    }
+
+    pub fn drop(&mut self, reason: Dropped, language: &str, kind: &str, line: &str) {
+        let key = (reason, language.to_string(), kind.to_string());
+        let tags = self.dropped.entry(key).or_insert_with(|| DroppedTags {
+            reason,
+            language: language.to_string(),
+            kind: kind.to_string(),
+            count: 0,
+            samples: vec![],
+        });
+        tags.count += 1;
+        if tags.samples.len() < SAMPLES {
+            tags.samples.push(line.to_string());
+        }
+    }
+
+    pub fn coverage(&self) -> Coverage {
+        let mut dropped: Vec<DroppedTags> = self.dropped.values().cloned().collect();
+        dropped.sort_by(|a, b| {
+            (a.reason, b.count, &a.language, &a.kind).cmp(&(
+                b.reason,
+                a.count,
+                &b.language,
+                &b.kind,
+            ))
+        });
+
+        let lost: usize = dropped
+            .iter()
+            .filter(|tags| !tags.reason.is_kept())
+            .map(|tags| tags.count)
+            .sum();
+        Coverage {
+            lines: self.lines,
+            parsed: self.lines.saturating_sub(lost),
+            dropped,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctags::ctags_coverage::{Diagnostics, Dropped, SAMPLES};
+
+    #[test]
+    fn should_count_and_sample_dropped_lines() {
+        let mut diagnostics = Diagnostics::default();
+        for index in 0..5 {
+            diagnostics.count_line();
+            diagnostics.drop(
+                Dropped::NoScope,
+                "Java",
+                "interface",
+                &format!("I{}", index),
+            );
+        }
+        diagnostics.count_line();
+        diagnostics.drop(Dropped::NoDataType, "Java", "field", "name");
+        diagnostics.count_line();
+        diagnostics.drop(Dropped::Unparsed, "", "", "broken");
+        diagnostics.count_line();
+
+        let coverage = diagnostics.coverage();
+
+        assert_eq!(8, coverage.lines);
+        assert_eq!(2, coverage.parsed);
+        assert_eq!(3, coverage.dropped.len());
+        assert_eq!(Dropped::Unparsed, coverage.dropped[0].reason);
+        assert_eq!(Dropped::NoScope, coverage.dropped[1].reason);
+        assert_eq!(5, coverage.dropped[1].count);
+        assert_eq!(SAMPLES, coverage.dropped[1].samples.len());
+        assert_eq!(Dropped::NoDataType, coverage.dropped[2].reason);
+        assert!(coverage
+            .summary()
+            .starts_with("coverage: 2 of 8 tag lines parsed (25.0%)"));
+    }
+}
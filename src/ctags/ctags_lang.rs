@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+
+use failure::{format_err, Error};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Settings of a language in `[languages.<name>]` of the configuration, `<name>` is the language
+/// name of ctags, like `Java`, or a new one defined by `define`, like `Protobuf`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct CtagsLanguage {
+    /// options passed to ctags as they are, like `--kinds-Java=+l`
+    pub options: Vec<String>,
+    /// define a language unknown to ctags by `--langdef`, its tags come from `regex`
+    pub define: bool,
+    /// file extensions mapped to the language, like `proto` for `.proto` files
+    pub extensions: Vec<String>,
+    /// `--regex-<name>` rules, like `/^message[ \t]+([A-Za-z0-9_]+)/\1/m,message/{scope=push}`
+    pub regex: Vec<String>,
+    /// kinds of tags parsed as classes, like `message`, tags of other kinds in the scope of
+    /// these classes are parsed by the kinds below
+    pub classes: Vec<String>,
+    /// kinds of tags parsed as members, like `field`
+    pub members: Vec<String>,
+    /// kinds of tags parsed as methods, like `rpc`
+    pub methods: Vec<String>,
+    /// regex with a `type` group for the data type of members and return type of methods,
+    /// matched against the source line of tags, like `^\s*(repeated\s+)?(?P<type>[\w.]+)\s`
+    pub data_type: String,
+}
+
+impl CtagsLanguage {
+    /// Returns options of ctags for the language `name`, a language is defined before its
+    /// extensions and rules.
+    pub fn ctags_options(&self, name: &str) -> Vec<String> {
+        let mut options = vec![];
+        if self.define {
+            options.push(format!("--langdef={}", name));
+        }
+        for extension in &self.extensions {
+            let extension = extension.trim_start_matches('.');
+            options.push(format!("--map-{}=+.{}", name, extension));
+        }
+        for regex in &self.regex {
+            options.push(format!("--regex-{}={}", name, regex));
+        }
+        options.extend(self.options.iter().cloned());
+        options
+    }
+
+    /// Kinds of regex languages are mapped by `classes`, `members` and `methods`, others are
+    /// parsed like the languages built in ctags.
+    pub fn maps_kinds(&self) -> bool {
+        !self.classes.is_empty()
+    }
+
+    /// Checks `data_type` of the language `name`.
+    pub fn validate(&self, name: &str) -> Result<(), Error> {
+        if let Some(re) = self.data_type_regex(name)? {
+            if !re.capture_names().any(|group| group == Some("type")) {
+                return Err(format_err!(
+                    "data_type of language {} needs a `type` group: {}",
+                    name,
+                    self.data_type
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn data_type_regex(&self, name: &str) -> Result<Option<Regex>, Error> {
+        if self.data_type.is_empty() {
+            return Ok(None);
+        }
+        Regex::new(&self.data_type)
+            .map(Some)
+            .map_err(|err| format_err!("invalid data_type of language {}: {}", name, err))
+    }
+}
+
+/// Returns options of ctags for all languages.
+pub fn ctags_options(languages: &BTreeMap<String, CtagsLanguage>) -> Vec<String> {
+    languages
+        .iter()
+        .flat_map(|(name, language)| language.ctags_options(name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::ctags::ctags_lang::{ctags_options, CtagsLanguage};
+
+    #[test]
+    fn should_define_language_before_its_rules() {
+        let mut languages = BTreeMap::new();
+        languages.insert(
+            "Java".to_string(),
+            CtagsLanguage {
+                options: vec!["--kinds-Java=+l".to_string()],
+                ..CtagsLanguage::default()
+            },
+        );
+        languages.insert(
+            "Protobuf".to_string(),
+            CtagsLanguage {
+                define: true,
+                extensions: vec!["proto".to_string()],
+                regex: vec!["/^message[ \\t]+([A-Za-z0-9_]+)/\\1/m,message/".to_string()],
+                ..CtagsLanguage::default()
+            },
+        );
+
+        assert_eq!(
+            vec![
+                "--kinds-Java=+l",
+                "--langdef=Protobuf",
+                "--map-Protobuf=+.proto",
+                "--regex-Protobuf=/^message[ \\t]+([A-Za-z0-9_]+)/\\1/m,message/",
+            ],
+            ctags_options(&languages)
+        );
+    }
+
+    #[test]
+    fn should_validate_data_type() {
+        let mut language = CtagsLanguage::default();
+        assert!(language.validate("Protobuf").is_ok());
+
+        language.data_type = r"^\s*(?P<type>[\w.]+)\s".to_string();
+        assert!(language.validate("Protobuf").is_ok());
+
+        language.data_type = r"^\s*([\w.]+)\s".to_string();
+        assert!(language.validate("Protobuf").is_err());
+        language.data_type = r"^\s*(?P<type>[\w.]+\s".to_string();
+        assert!(language.validate("Protobuf").is_err());
+    }
+}
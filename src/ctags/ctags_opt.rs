@@ -48,6 +48,10 @@ pub struct Opt {
     #[structopt(short = "s", long = "stat")]
     pub stat: bool,
 
+    /// Seconds before a ctags process is killed ( 0 means no timeout )
+    #[structopt(long = "timeout", default_value = "0")]
+    pub timeout: u64,
+
     /// Filename of input file list
     #[structopt(short = "L", long = "list")]
     pub list: Option<String>,
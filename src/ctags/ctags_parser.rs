@@ -13,9 +13,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 use crate::coco_struct::{ClassInfo, MemberInfo, MethodInfo};
+use crate::ctags::ctags_coverage::{Coverage, Diagnostics, Dropped};
+use crate::ctags::ctags_lang::CtagsLanguage;
+use crate::ctags::rust_module::CrateRoots;
+use crate::ctags::{access, rust_module};
 use crate::ParseOption;
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
@@ -24,38 +28,164 @@ use std::str::Lines;
 #[derive(Default)]
 pub struct CtagsParser {
     pub(crate) option: ParseOption,
-    class_map: HashMap<String, ClassInfo>,
+    /// classes in the order of tags, a class tagged again in the same scope replaces the former
+    classes: Vec<ClassInfo>,
+    /// index of `classes` by file and scope path, like `Order.java` and `Order.Item`
+    class_index: HashMap<(String, String), usize>,
+    /// indexes of `classes` by name, for members tagged out of the file of their class
+    classes_by_name: HashMap<String, Vec<usize>>,
+    /// members and methods in the order of tags, they are owned by their classes in `classes`,
+    /// since a class may be tagged after its members
+    declarations: Vec<Declaration>,
+    /// compiled `data_type` of regex languages in `option.languages`
+    data_types: HashMap<String, Option<Regex>>,
+    /// tag lines dropped by the parser, counted if `option.coverage_file` is set
+    diagnostics: Diagnostics,
+    /// crate roots of Rust files, found in the dir of ctags before parsing
+    pub(crate) crate_roots: CrateRoots,
+    /// source lines of Rust impls, structs, enums and traits by file and line, members take the
+    /// visibility of the trait impl or enum they're declared in
+    rust_items: HashMap<String, BTreeMap<i32, String>>,
+}
+
+/// kinds of scopes which own members in languages built in ctags
+const SCOPE_KINDS: [&str; 3] = ["class", "implementation", "struct"];
+
+/// kinds of Rust items which own members and methods
+const RUST_ITEM_KINDS: [&str; 5] = ["implementation", "struct", "enum", "union", "interface"];
+
+/// kinds of scopes which enclose classes in languages built in ctags, like the namespace of a C#
+/// class, Rust modules are resolved by `rust_module` instead
+const ENCLOSING_KINDS: [&str; 7] = [
+    "class",
+    "struct",
+    "interface",
+    "enum",
+    "union",
+    "namespace",
+    "package",
+];
+
+/// A member or method with the scope of its class, like `SubscriberRegistry.MethodIdentifier`.
+struct Declaration {
+    file: String,
+    scope: String,
+    lang: String,
+    declared: Declared,
+    /// the tag line, only kept for the coverage report
+    tag: String,
+    /// why it is missing in the model or without data type, unless its class is never tagged
+    dropped: Option<Dropped>,
+}
+
+enum Declared {
+    Member(MemberInfo),
+    Method(MethodInfo),
+    /// other kinds, like nested classes, only update the language of their scope
+    Other,
+}
+
+/// A line of ctags output in the extended format:
+/// `name<TAB>file<TAB>address;"<TAB>kind<TAB>key:value...`.
+/// The address is a line number or a search pattern, patterns keep the tabs of source lines.
+struct TagLine<'a> {
+    name: &'a str,
+    file: &'a str,
+    address: &'a str,
+    kind: &'a str,
+    fields: &'a str,
+}
+
+impl<'a> TagLine<'a> {
+    fn parse(line: &'a str) -> Option<TagLine<'a>> {
+        let (name, rest) = line.split_once('\t')?;
+        let (file, rest) = rest.split_once('\t')?;
+        // the address ends with the first `;"` followed by a tab or the end of line
+        let end = match rest.find(";\"\t") {
+            Some(index) => index + 2,
+            None if rest.ends_with(";\"") => rest.len(),
+            None => return None,
+        };
+        let (address, rest) = rest.split_at(end);
+        let rest = rest.strip_prefix('\t').unwrap_or(rest);
+        let (kind, fields) = rest.split_once('\t').unwrap_or((rest, ""));
+
+        Some(TagLine {
+            name,
+            file,
+            address,
+            kind,
+            fields,
+        })
+    }
+
+    /// Returns the leading chars of the first `key:value` field accepted by `valid`, if any.
+    fn field(&self, key: &str, valid: fn(char) -> bool) -> Option<&'a str> {
+        self.fields.split('\t').find_map(|field| {
+            let value = field.strip_prefix(key)?.strip_prefix(':')?;
+            Some(leading(value, valid)).filter(|value| !value.is_empty())
+        })
+    }
+
+    /// Returns the scope of members in one of `kinds`, like `class:SubscriberRegistry.Identifier`.
+    fn scope<S: AsRef<str>>(&self, kinds: &[S]) -> Option<&'a str> {
+        self.fields.split('\t').find_map(|field| {
+            let (key, value) = field.split_once(':')?;
+            if !kinds.iter().any(|kind| kind.as_ref() == key) {
+                return None;
+            }
+            let value = leading(value, |c| is_word(c) || c == '.' || c == ':');
+            Some(value).filter(|value| !value.is_empty())
+        })
+    }
+
+    /// Members need an alphabetic kind followed by their line number.
+    fn member_line_no(&self) -> Option<i32> {
+        if self.kind.is_empty() || !self.kind.chars().all(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        let name = self.name.strip_prefix('#').unwrap_or(self.name);
+        if name.is_empty() || !name.chars().all(is_word) {
+            return None;
+        }
+
+        let line_no = self.fields.split('\t').next()?.strip_prefix("line:")?;
+        let line_no = leading(line_no, |c| c.is_ascii_digit());
+        if line_no.is_empty() {
+            return None;
+        }
+        Some(line_no.parse().unwrap_or(0))
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn is_language(c: char) -> bool {
+    is_word(c) || c == '#'
+}
+
+/// Returns the scope with `.` separators only, like `Shop.Order` of `Shop::Order`.
+fn scope_path(scope: &str) -> String {
+    scope.replace("::", ".")
+}
+
+/// Returns the directory of `file`, without the file name.
+fn directory_of(file: &str) -> &str {
+    file.rfind(['/', '\\']).map_or("", |index| &file[..index])
+}
+
+fn leading(value: &str, valid: fn(char) -> bool) -> &str {
+    let end = value.find(|c: char| !valid(c)).unwrap_or(value.len());
+    &value[..end]
 }
 
 lazy_static! {
-    static ref CLASS_RE: Regex = Regex::new(
-        r"(?x)
-^(?P<class_name>[A-Za-z0-9_]+)
-\t(?P<file_name>([^\t]+))
-\t([^\t]+)\t(class|struct)"
-    )
-    .unwrap();
-    static ref INHERITS_RE: Regex = Regex::new(r"inherits:(?P<inherits>[A-Za-z0-9_:,]+)").unwrap();
-    static ref AVAILABLE_RE: Regex = Regex::new(
-        r#"(?x)
-^(?P<name>[A-Za-z0-9_]+)
-\t(?P<data_type>[^\t]+)
-\t([^\t]+.*?")
-\t(?P<tag_type>[A-Za-z]+)
-\tline:(?P<line_no>[0-9]+)"#
-    )
-    .unwrap();
-    static ref RE_CLASS: Regex = Regex::new(
-        r"(?x)
-(class|implementation|struct):(?P<class_name>[A-Za-z0-9_\.]+)"
-    )
-    .unwrap();
-    static ref RE_ACCESS: Regex = Regex::new(r"access:(?P<access>[A-Za-z0-9_]+)").unwrap();
-    static ref RE_LANGUAGE: Regex = Regex::new(r"language:(?P<language>[A-Za-z0-9_\#]+)").unwrap();
     static ref RE_TYPE: Regex =
         Regex::new(r"/\^([ ]*)(?P<datatype>[A-Za-z0-9_.]+)([^A-Za-z0-9_]+)(.*)\$/").unwrap();
     static ref RUST_TYPE: Regex = Regex::new(
-        r"/\^([ ]*)(?P<field>[A-Za-z0-9_.\s]+)\s*:(\t|\s)*(?P<datatype>[A-Za-z0-9_.<>]+)"
+        r"/\^([ ]*)(?P<field>[A-Za-z0-9_.\s()]+)\s*:(\t|\s)*(?P<datatype>[A-Za-z0-9_.<>]+)"
     )
     .unwrap();
     static ref PURE_RUST_TYPE: Regex =
@@ -69,7 +199,6 @@ lazy_static! {
     .unwrap();
     static ref TYPE_SCRIPT_TYPE: Regex =
         Regex::new(r"/\^([ |\t]*).*:([ |\t]*)(?P<datatype>[A-Za-z0-9_.<>\[\]]+).*\$/").unwrap();
-    static ref PARAMETER: Regex = Regex::new(r"/.*\((?P<parameters>(.*?))\).*/").unwrap();
     static ref RUST_RETURN_TYPE: Regex =
         Regex::new(r"\s->\s(?P<datatype>[A-Za-z0-9_.]+)\s").unwrap();
     static ref TYPE_KEYWORDS: [&'static str; 18] = [
@@ -97,15 +226,9 @@ lazy_static! {
 impl CtagsParser {
     pub fn parse_str(all_lines: Vec<Lines>) -> CtagsParser {
         let mut parser = CtagsParser::default();
-        for lines in all_lines.clone() {
-            for line in lines.into_iter() {
-                parser.parse_class(line);
-            }
-        }
-
         for lines in all_lines {
-            for line in lines.into_iter() {
-                parser.parse_method_methods(line);
+            for line in lines {
+                parser.parse_line(line);
             }
         }
 
@@ -114,236 +237,600 @@ impl CtagsParser {
 
     pub fn parse(dir: PathBuf) -> CtagsParser {
         let file = File::open(format!("{}", dir.display()).as_str()).expect("cannot find file");
-        let reader = BufReader::new(file);
+        Self::parse_reader(BufReader::new(file))
+    }
 
+    /// Parses tags line by line, so the output of ctags is never held as a whole.
+    pub fn parse_reader<R: BufRead>(reader: R) -> CtagsParser {
         let mut parser = CtagsParser::default();
         for line in reader.lines().flatten() {
-            parser.parse_class(line.as_str());
-        }
-
-        let file = File::open(format!("{}", dir.display()).as_str()).expect("cannot find file");
-        let reader = BufReader::new(file);
-        for line in reader.lines().flatten() {
-            parser.parse_method_methods(line.as_str());
+            parser.parse_line(line.as_str());
         }
 
         parser
     }
 
-    pub fn parse_class(&mut self, line: &str) {
-        if let Some(captures) = CLASS_RE.captures(line) {
-            let class_name = &captures["class_name"];
-            let file_name = &captures["file_name"];
-            let mut clazz = ClassInfo::new(class_name);
-            clazz.file = file_name.to_string();
+    /// Parses one line of ctags output, members are owned by their classes in `classes`.
+    pub fn parse_line(&mut self, line: &str) {
+        // pseudo tags, like `!_TAG_FILE_SORTED`
+        if line.starts_with("!_") {
+            return;
+        }
+        let reports_coverage = self.reports_coverage();
+        if reports_coverage {
+            self.diagnostics.count_line();
+        }
 
-            if let Some(inherits_capts) = INHERITS_RE.captures(line) {
-                let inherits_str = &inherits_capts["inherits"];
-                let inherits = inherits_str
-                    .split(',')
-                    .map(|s| s.to_string())
-                    .collect::<Vec<String>>();
+        let tag = match TagLine::parse(line) {
+            Some(tag) => tag,
+            None => {
+                if reports_coverage {
+                    self.diagnostics.drop(Dropped::Unparsed, "", "", line);
+                }
+                return;
+            }
+        };
 
-                clazz.parents = inherits;
+        let language = tag.field("language", is_language).unwrap_or_default();
+        if language == "Rust" && RUST_ITEM_KINDS.contains(&tag.kind) {
+            self.add_rust_item(&tag);
+        }
+        let is_class = match self.regex_language(language) {
+            Some(mapped) => mapped.classes.iter().any(|kind| kind == tag.kind),
+            None => tag.kind == "class" || tag.kind == "struct",
+        };
+        if is_class && !tag.name.chars().all(is_word) {
+            if reports_coverage {
+                self.diagnostics
+                    .drop(Dropped::ClassName, language, tag.kind, line);
             }
+            return;
+        }
 
-            self.class_map.insert(class_name.to_string(), clazz);
+        if is_class {
+            self.parse_class(&tag);
+        }
+        if let Err(reason) = self.parse_declaration(&tag, line, is_class) {
+            // classes out of scope are parsed by `parse_class`
+            if reports_coverage && !is_class {
+                self.diagnostics.drop(reason, language, tag.kind, line);
+            }
         }
     }
 
-    pub fn parse_method_methods(&mut self, line: &str) {
-        if !AVAILABLE_RE.is_match(line) {
-            return;
+    /// Returns the count of tag lines parsed into classes, with the ones dropped by the parser,
+    /// only lines parsed with `option.coverage_file` are counted.
+    pub fn coverage(&self) -> Coverage {
+        let mut diagnostics = self.diagnostics.clone();
+        for declaration in &self.declarations {
+            let reason = if self.owner_of(declaration).is_none() {
+                Some(Dropped::Orphan)
+            } else {
+                declaration.dropped
+            };
+            // nested classes in the scope of an unknown class are still parsed
+            let is_class =
+                matches!(declaration.declared, Declared::Other) && declaration.dropped.is_none();
+            if let (Some(reason), false) = (reason, is_class) {
+                let kind = TagLine::parse(&declaration.tag)
+                    .map(|tag| tag.kind)
+                    .unwrap_or_default();
+                diagnostics.drop(reason, &declaration.lang, kind, &declaration.tag);
+            }
         }
 
-        let captures = AVAILABLE_RE.captures(line).unwrap();
+        diagnostics.coverage()
+    }
 
-        let clazz;
-        match self.lookup_class_from_map(line) {
+    fn reports_coverage(&self) -> bool {
+        !self.option.coverage_file.is_empty()
+    }
+
+    fn add_rust_item(&mut self, tag: &TagLine) {
+        let line_no = match tag.field("line", |c| c.is_ascii_digit()) {
+            Some(line_no) => line_no.parse().unwrap_or(0),
             None => return,
-            Some(clz) => clazz = clz,
+        };
+        self.rust_items
+            .entry(tag.file.to_string())
+            .or_default()
+            .insert(line_no, access::source_of(tag.address).to_string());
+    }
+
+    fn parse_class(&mut self, tag: &TagLine) {
+        let mut clazz = ClassInfo::new(tag.name);
+        clazz.file = tag.file.to_string();
+        clazz.kind = tag.kind.to_string();
+
+        if let Some(inherits) = tag.field("inherits", |c| is_word(c) || c == ':' || c == ',') {
+            clazz.parents = inherits.split(',').map(|s| s.to_string()).collect();
         }
 
-        let name = &captures["name"];
-        let tag_type = &captures["tag_type"];
-        let line_no: i32 = (&captures["line_no"]).parse().unwrap_or(0);
+        if let Some(line_no) = tag.field("line", |c| c.is_ascii_digit()) {
+            clazz.line_no = line_no.parse().unwrap_or(0);
+        }
 
-        let mut access = "".to_string();
-        if let Some(capts) = RE_ACCESS.captures(line) {
-            let match_access = &capts["access"];
-            match match_access {
-                "public" => access = "+".to_string(),
-                "private" => access = "-".to_string(),
-                "protected" => access = "#".to_string(),
-                &_ => {}
+        if let Some(language) = tag.field("language", is_language) {
+            clazz.lang = language.to_string();
+        }
+
+        let mut path = tag.name.to_string();
+        if clazz.lang == "Rust" {
+            let inline_module = tag
+                .field("module", |c| is_word(c) || c == ':')
+                .unwrap_or_default();
+            clazz.package = rust_module::with_inline_module(
+                &self.crate_roots.module_path(tag.file),
+                inline_module,
+            );
+        } else {
+            // the package statement of Java and Kotlin files is added by `source::read_sources`
+            let enclosing = match self.regex_language(&clazz.lang) {
+                Some(mapped) => tag.scope(&mapped.classes),
+                None => tag.scope(&ENCLOSING_KINDS),
+            };
+            if let Some(enclosing) = enclosing {
+                path = format!("{}.{}", scope_path(enclosing), tag.name);
+                clazz.package = enclosing.to_string();
             }
         }
 
-        let lang_capts = RE_LANGUAGE.captures(line).unwrap();
-        let language = &lang_capts["language"];
-        clazz.lang = language.to_string();
+        let key = (tag.file.to_string(), path);
+        match self.class_index.get(&key) {
+            Some(&index) => self.classes[index] = clazz,
+            None => {
+                let index = self.classes.len();
+                self.classes_by_name
+                    .entry(clazz.name.clone())
+                    .or_default()
+                    .push(index);
+                self.classes.push(clazz);
+                self.class_index.insert(key, index);
+            }
+        }
+    }
+
+    fn parse_declaration(
+        &mut self,
+        tag: &TagLine,
+        line: &str,
+        is_class: bool,
+    ) -> Result<(), Dropped> {
+        let line_no = match tag.member_line_no() {
+            Some(line_no) => line_no,
+            None => return Err(Dropped::NoLine),
+        };
+        let language = tag.field("language", is_language).unwrap_or_default();
+        self.compile_data_type(language);
+        let mapped = self.regex_language(language);
+        let scope = match mapped {
+            Some(mapped) => tag.scope(&mapped.classes),
+            None => tag.scope(&SCOPE_KINDS),
+        };
+        let scope = match scope {
+            Some(scope) => scope,
+            None => return Err(Dropped::NoScope),
+        };
+        let class_name = Self::class_name(scope);
+        if class_name.is_empty() {
+            return Err(Dropped::NoScope);
+        }
+
+        let ctags_access = tag.field("access", is_word).unwrap_or_default().to_string();
 
         let mut data_type = "".to_string();
         let mut pure_data_type = "".to_string();
         match language {
+            _ if mapped.is_some() => {
+                let source = access::source_of(tag.address);
+                if let Some(re) = self.data_types.get(language).and_then(|re| re.as_ref()) {
+                    if let Some(capts) = re.captures(source) {
+                        data_type = capts
+                            .name("type")
+                            .map(|ty| ty.as_str().to_string())
+                            .unwrap_or_default();
+                    }
+                }
+            }
             "Java" | "C#" | "C++" => {
-                let without_keywords = CtagsParser::remove_keywords(line.to_string());
+                let without_keywords = CtagsParser::remove_keywords(tag.address.to_string());
                 if let Some(capts) = RE_TYPE.captures(without_keywords.as_str()) {
                     data_type = (&capts["datatype"]).to_string();
                 }
             }
             "Rust" => {
-                if let Some(capts) = RUST_TYPE.captures(line) {
+                if let Some(capts) = RUST_TYPE.captures(tag.address) {
                     data_type = (&capts["datatype"]).to_string();
 
-                    let field_with_access = (&capts["field"]).to_string();
-                    access = Self::parse_rust_access(field_with_access);
-
                     if let Some(ty) = PURE_RUST_TYPE.captures(data_type.as_str()) {
                         pure_data_type = (&ty["datatype"]).to_string();
                     }
-                } else if let Some(capts) = RUST_RETURN_TYPE.captures(line) {
+                } else if let Some(capts) = RUST_RETURN_TYPE.captures(tag.address) {
                     data_type = (&capts["datatype"]).to_string();
                     if data_type == "Self" {
-                        data_type = clazz.name.to_string()
+                        data_type = class_name.to_string()
                     }
                 }
             }
             "Go" => {
-                if let Some(capts) = GO_TYPE.captures(line) {
+                if let Some(capts) = GO_TYPE.captures(tag.address) {
                     data_type = (&capts["datatype"]).to_string();
                 }
             }
             "TypeScript" => {
-                if let Some(capts) = TYPE_SCRIPT_TYPE.captures(line) {
+                if let Some(capts) = TYPE_SCRIPT_TYPE.captures(tag.address) {
                     data_type = (&capts["datatype"]).to_string();
                 }
             }
             _ => {}
         }
 
-        if tag_type.eq("member") || tag_type.eq("field") || tag_type.eq("property") {
-            let mut member = MemberInfo::new(name, access, data_type);
-            member.line_no = line_no;
-            if !pure_data_type.is_empty() {
-                member.pure_data_type = pure_data_type;
+        let typed = !data_type.is_empty();
+        let access = access::visibility(language, tag.name, tag.address, &ctags_access);
+        let name = tag.name.trim_start_matches('#');
+
+        let (is_member, is_method) = match mapped {
+            Some(mapped) => (
+                mapped.members.iter().any(|kind| kind == tag.kind),
+                mapped.methods.iter().any(|kind| kind == tag.kind),
+            ),
+            None => (
+                matches!(tag.kind, "member" | "field" | "property"),
+                matches!(tag.kind, "method" | "function"),
+            ),
+        };
+        let declared = match tag.kind {
+            _ if is_member => {
+                let mut member = MemberInfo::new(name, access, data_type);
+                member.line_no = line_no;
+                if !pure_data_type.is_empty() {
+                    member.pure_data_type = pure_data_type;
+                }
+                Declared::Member(member)
             }
-            clazz.members.push(member);
-        } else if tag_type.eq("method") || tag_type.eq("function") {
-            let parameters = Self::pick_parameter_list(&captures[3]);
-            let mut method = MethodInfo::new(name, access, parameters, data_type);
-            method.line_no = line_no;
-            if !pure_data_type.is_empty() {
-                method.pure_return_type = pure_data_type;
+            _ if is_method => {
+                let parameters = Self::pick_parameter_list(tag.address);
+                let mut method = MethodInfo::new(name, access, parameters, data_type);
+                method.line_no = line_no;
+                if !pure_data_type.is_empty() {
+                    method.pure_return_type = pure_data_type;
+                }
+                Declared::Method(method)
             }
-            clazz.methods.push(method);
-        }
+            _ => Declared::Other,
+        };
+        let dropped = match declared {
+            Declared::Other if !is_class => Some(Dropped::UnknownKind),
+            Declared::Other => None,
+            _ if !typed => Some(Dropped::NoDataType),
+            _ => None,
+        };
+
+        let file = tag.file.to_string();
+        let tag = if self.reports_coverage() {
+            line.to_string()
+        } else {
+            "".to_string()
+        };
+        self.declarations.push(Declaration {
+            file,
+            scope: scope.to_string(),
+            lang: language.to_string(),
+            declared,
+            tag,
+            dropped,
+        });
+        Ok(())
     }
 
-    fn parse_rust_access(field_with_access: String) -> String {
-        let split = field_with_access
-            .split(' ')
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>();
+    /// Returns the language in `option.languages` if its kinds are mapped to classes and members.
+    fn regex_language(&self, language: &str) -> Option<&CtagsLanguage> {
+        self.option
+            .languages
+            .get(language)
+            .filter(|mapped| mapped.maps_kinds())
+    }
 
-        if split.len() > 1 {
-            return if split[0] == "pub" {
-                "+".to_string()
-            } else {
-                "#".to_string()
-            };
+    /// invalid regex is reported by `CtagsLanguage::validate` before parsing
+    fn compile_data_type(&mut self, language: &str) {
+        if self.data_types.contains_key(language) {
+            return;
+        }
+        if let Some(mapped) = self.regex_language(language) {
+            let re = mapped.data_type_regex(language).ok().flatten();
+            self.data_types.insert(language.to_string(), re);
         }
-
-        "-".to_string()
     }
 
     pub fn remove_keywords(mut line: String) -> String {
         for keyword in TYPE_KEYWORDS.iter() {
-            line = line.replacen(keyword, "", 1)
+            if let Some(index) = line.find(keyword) {
+                line.replace_range(index..index + keyword.len(), "");
+            }
         }
 
         line
     }
 
-    fn lookup_class_from_map(&mut self, line: &str) -> Option<&mut ClassInfo> {
-        let mut class_name = "".to_string();
+    /// the class of a scope is its last segment, like `MethodIdentifier` of
+    /// `SubscriberRegistry.MethodIdentifier`
+    fn class_name(scope: &str) -> &str {
+        scope.rsplit(['.', ':']).next().unwrap_or_default()
+    }
 
-        if let Some(captures) = RE_CLASS.captures(line) {
-            class_name = captures["class_name"].to_string();
+    /// Returns the index of the class which owns `declaration`, by the file and scope path of the
+    /// declaration, or else by the name of its scope, like methods in a C++ source file or a Rust
+    /// impl in another module: a class in the same file, the only class of the name, or one in
+    /// the same directory. Otherwise the declaration is an orphan.
+    fn owner_of(&self, declaration: &Declaration) -> Option<usize> {
+        let key = (declaration.file.clone(), scope_path(&declaration.scope));
+        if let Some(&index) = self.class_index.get(&key) {
+            return Some(index);
         }
 
-        let package = class_name.clone();
-        let split = class_name.split('.');
-        if let Some(last) = split.last() {
-            class_name = last.to_string();
+        let candidates = self
+            .classes_by_name
+            .get(Self::class_name(&declaration.scope))?;
+        let in_file = |index: &&usize| self.classes[**index].file == declaration.file;
+        let in_dir = |index: &&usize| {
+            directory_of(&self.classes[**index].file) == directory_of(&declaration.file)
+        };
+        match candidates.as_slice() {
+            [index] => Some(*index),
+            _ => candidates
+                .iter()
+                .find(in_file)
+                .or_else(|| candidates.iter().find(in_dir))
+                .copied(),
         }
+    }
 
-        if class_name.is_empty() {
-            return None;
+    /// Returns classes with their members and methods, the parser is consumed, so `coverage`
+    /// is taken before it.
+    pub fn classes(self) -> Vec<ClassInfo> {
+        let owners: Vec<Option<usize>> = self
+            .declarations
+            .iter()
+            .map(|declaration| self.owner_of(declaration))
+            .collect();
+
+        let mut classes = self.classes;
+        for (mut declaration, owner) in self.declarations.into_iter().zip(owners) {
+            if let Some(index) = owner {
+                if declaration.lang == "Rust" {
+                    Self::inherit_rust_visibility(&self.rust_items, &mut declaration);
+                }
+                let clazz = &mut classes[index];
+                clazz.lang = declaration.lang;
+                match declaration.declared {
+                    Declared::Member(member) => clazz.members.push(member),
+                    Declared::Method(method) => clazz.methods.push(method),
+                    Declared::Other => {}
+                }
+            }
         }
 
-        let clazz: &mut ClassInfo;
-        match self.class_map.get_mut(&*class_name) {
-            Some(clz) => {
-                clazz = clz;
-            }
-            None => {
-                return None;
-            }
-        };
+        for clazz in classes.iter_mut() {
+            Self::sort_declarations(clazz, &self.option);
+        }
+        // classes of the same name are kept apart by their package and file
+        classes.sort_by_cached_key(|clazz| {
+            (
+                clazz.name.to_lowercase(),
+                clazz.package.clone(),
+                clazz.file.clone(),
+            )
+        });
+
+        classes
+    }
 
-        clazz.package = package;
-        Some(clazz)
+    /// Rust items are tagged in any order, so the item of a declaration is the closest one before
+    /// it in the same file.
+    fn inherit_rust_visibility(
+        rust_items: &HashMap<String, BTreeMap<i32, String>>,
+        declaration: &mut Declaration,
+    ) {
+        let (access, line_no) = match &mut declaration.declared {
+            Declared::Member(member) => (&mut member.access, member.line_no),
+            Declared::Method(method) => (&mut method.access, method.line_no),
+            Declared::Other => return,
+        };
+        let item = rust_items
+            .get(&declaration.file)
+            .and_then(|items| items.range(..=line_no).next_back());
+        if let Some((_, item)) = item {
+            *access = access::rust_visibility_in(item, access);
+        }
     }
 
-    pub fn classes(&self) -> Vec<ClassInfo> {
-        let mut classes = vec![];
-        for (_str, clz) in &self.class_map {
-            let mut clazz = clz.clone();
+    /// Sorts members and methods of `clazz` by name, and merges them by `merge_method_name` and
+    /// `merge_overloads` of `option`, also for classes loaded from a saved model.
+    pub fn sort_declarations(clazz: &mut ClassInfo, option: &ParseOption) {
+        if option.merge_method_name || option.merge_overloads {
+            Self::dedup_declarations(clazz);
+        } else {
             clazz
                 .methods
                 .sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
             clazz
                 .members
                 .sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        }
 
-            if self.option.merge_method_name {
-                clazz
-                    .methods
-                    .dedup_by(|a, b| a.name.eq_ignore_ascii_case(&*b.name));
-                clazz
-                    .members
-                    .dedup_by(|a, b| a.name.eq_ignore_ascii_case(&*b.name));
+        if option.merge_overloads {
+            let methods = std::mem::take(&mut clazz.methods);
+            clazz.methods = Self::merge_overloads(methods);
+        }
+    }
+
+    /// Removes true duplicates only, like the same method declared in a header and a source file:
+    /// methods need the same name and parameter types, members the same name and data type.
+    fn dedup_declarations(clazz: &mut ClassInfo) {
+        let lang = clazz.lang.clone();
+        clazz.methods.sort_by_cached_key(|method| {
+            (
+                method.name.to_lowercase(),
+                method.name.clone(),
+                Self::parameter_types(&lang, &method.parameters),
+            )
+        });
+        clazz.methods.dedup_by(|a, b| {
+            a.name == b.name
+                && Self::parameter_types(&lang, &a.parameters)
+                    == Self::parameter_types(&lang, &b.parameters)
+        });
+
+        clazz.members.sort_by_cached_key(|member| {
+            (
+                member.name.to_lowercase(),
+                member.name.clone(),
+                member.data_type.clone(),
+            )
+        });
+        clazz
+            .members
+            .dedup_by(|a, b| a.name == b.name && a.data_type == b.data_type);
+    }
+
+    /// Groups overloads (same name, different parameter types) of sorted methods into one entry.
+    fn merge_overloads(methods: Vec<MethodInfo>) -> Vec<MethodInfo> {
+        let mut merged: Vec<MethodInfo> = vec![];
+        for mut method in methods {
+            if let Some(last) = merged.last_mut() {
+                if last.name == method.name {
+                    last.overloads += method.overloads.max(1);
+                    continue;
+                }
             }
 
-            classes.push(clazz);
+            // methods of a saved model may be merged already
+            method.overloads = method.overloads.max(1);
+            merged.push(method);
+        }
+        merged
+    }
+
+    /// Returns the types of parameters, which identify an overload together with the method name.
+    fn parameter_types(lang: &str, parameters: &[String]) -> Vec<String> {
+        let mut types: Vec<String> = parameters
+            .iter()
+            .map(|parameter| Self::parameter_type(lang, parameter))
+            .collect();
+
+        // Go parameters are all named or all types, names are grouped by the type after them,
+        // like `a, b int`
+        let is_named = |parameter: &String| parameter.trim().contains(char::is_whitespace);
+        if lang == "Go" && parameters.iter().any(is_named) {
+            let mut data_type = String::new();
+            for (parameter, ty) in parameters.iter().zip(types.iter_mut()).rev() {
+                if is_named(parameter) {
+                    data_type = ty.clone();
+                } else {
+                    *ty = data_type.clone();
+                }
+            }
         }
+        types
+    }
 
-        classes.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    fn parameter_type(lang: &str, parameter: &str) -> String {
+        // drop default values, but keep arrow function types like `(a: A) => void`
+        let mut declaration = parameter;
+        for (index, c) in parameter.char_indices() {
+            if c == '=' && !parameter[index + 1..].starts_with('>') {
+                declaration = &parameter[..index];
+                break;
+            }
+        }
+        let declaration = declaration.trim();
 
-        return classes;
+        match lang {
+            "Rust" | "TypeScript" | "JavaScript" | "Kotlin" | "Scala" | "Swift" | "Python" => {
+                match declaration.split_once(':') {
+                    Some((_, data_type)) => data_type.trim().to_string(),
+                    None => "".to_string(),
+                }
+            }
+            "Go" => match declaration.split_once(char::is_whitespace) {
+                Some((_, data_type)) => data_type.trim().to_string(),
+                None => declaration.to_string(),
+            },
+            _ => {
+                let words: Vec<&str> = declaration
+                    .split_whitespace()
+                    .filter(|word| !word.starts_with('@') && *word != "final")
+                    .collect();
+                if words.len() > 1 {
+                    words[..words.len() - 1].join(" ")
+                } else {
+                    words.join(" ")
+                }
+            }
+        }
     }
 
     fn pick_parameter_list(signature: &str) -> Vec<String> {
-        PARAMETER
-            .captures(signature)
-            .and_then(|cap| {
-                cap.name("parameters").map(|s| s.as_str()).and_then(|s| {
-                    if s.is_empty() {
-                        None
-                    } else {
-                        Some(s.split(',').map(|s| s.to_string()).collect())
-                    }
-                })
-            })
+        signature
+            .split('\n')
+            .find_map(Self::parameter_list)
+            .filter(|parameters| !parameters.is_empty())
+            .map(Self::split_parameters)
             .unwrap_or_default()
     }
+
+    /// Returns the content of the last brackets between slashes, like `/^  void send(Message m) {$/`,
+    /// which are closed before the last slash.
+    fn parameter_list(line: &str) -> Option<&str> {
+        let start = line.find('/')?;
+        let end = line.rfind('/')?;
+        line[..end]
+            .match_indices('(')
+            .rev()
+            .filter(|(open, _)| *open > start)
+            .find_map(|(open, _)| {
+                let close = line[open + 1..end].find(')')?;
+                Some(&line[open + 1..open + 1 + close])
+            })
+    }
+
+    /// Splits parameters by top level commas, so `Map<String, User> users` stays one parameter
+    fn split_parameters(parameters: &str) -> Vec<String> {
+        let mut result = vec![];
+        let mut depth = 0;
+        let mut current = String::new();
+        let mut previous = ' ';
+        for c in parameters.chars() {
+            match c {
+                // arrows like `=>` or `->` aren't closing brackets
+                '>' if previous == '=' || previous == '-' => {}
+                '<' | '(' | '[' | '{' => depth += 1,
+                '>' | ')' | ']' | '}' => depth -= 1,
+                ',' if depth == 0 => {
+                    result.push(current.clone());
+                    current.clear();
+                    continue;
+                }
+                _ => {}
+            }
+            previous = c;
+            current.push(c);
+        }
+        result.push(current);
+        result
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::ctags::ctags_coverage::Dropped;
+    use crate::ctags::ctags_lang::CtagsLanguage;
+    use crate::ctags::rust_module::CrateRoots;
+    use crate::source::read_sources;
     use crate::{CtagsParser, ParseOption};
+    use std::fs;
     use std::path::PathBuf;
+    use tempfile::TempDir;
 
     pub fn tags_dir() -> PathBuf {
         PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -370,6 +857,20 @@ MethodIdentifier	SubscriberRegistry.java	/^  private static final class MethodId
         assert_eq!("MethodIdentifier", first_method.return_type);
     }
 
+    #[test]
+    fn should_keep_generic_parameters_together() {
+        let str =
+            "save\tRepo.java\t/^    void save(Map<String, User> users, int size) {$/;\"\tmethod";
+        let parameters = CtagsParser::pick_parameter_list(str);
+        assert_eq!(
+            vec![
+                String::from("Map<String, User> users"),
+                String::from(" int size")
+            ],
+            parameters
+        );
+    }
+
     #[test]
     fn should_get_parameters() {
         let str = "MethodIdentifier	SubscriberRegistry.java	/^    MethodIdentifier(Method method) {$/;\"	method	line:239	language:Java	class:SubscriberRegistry.MethodIdentifier	access:default
@@ -405,7 +906,7 @@ render	graphviz_render.rs	/^    pub fn render(classes: &Vec<ClassInfo>, parse_op
 
         assert_eq!(1, classes.len());
         assert_eq!("render", classes[0].methods[0].name);
-        // assert_eq!("+", classes[0].methods[0].access);
+        assert_eq!("+", classes[0].methods[0].access);
     }
 
     #[test]
@@ -418,6 +919,7 @@ render	graphviz_render.rs	/^    pub fn render(classes: &Vec<ClassInfo>, parse_op
 
         assert_eq!("Java", classes[0].lang);
         assert_eq!("TypeName.java", classes[0].file);
+        assert_eq!("class", classes[0].kind);
 
         let first_method = classes[0].methods[0].clone();
         assert_eq!("description", first_method.name);
@@ -433,10 +935,13 @@ render	graphviz_render.rs	/^    pub fn render(classes: &Vec<ClassInfo>, parse_op
         assert_eq!(1, classes.len());
         assert_eq!("Rust", classes[0].lang);
         assert_eq!("coco_swagger/src/lib.rs", classes[0].file);
+        assert_eq!("struct", classes[0].kind);
         let methods = classes[0].methods.clone();
         assert_eq!(5, methods.len());
         assert_eq!("default", methods[0].name);
         assert_eq!("execute", methods[1].name);
+        // methods of `impl Default for` and `impl PluginInterface for`
+        assert!(methods.iter().all(|method| method.access == "+"));
     }
 
     #[test]
@@ -456,6 +961,163 @@ render	graphviz_render.rs	/^    pub fn render(classes: &Vec<ClassInfo>, parse_op
         assert_eq!(43, six_member.line_no)
     }
 
+    #[test]
+    pub fn should_use_module_path_as_rust_package() {
+        let str = r#"GraphvizRender	src/render/graphviz_render.rs	/^pub struct GraphvizRender;$/;"	struct	line:11	language:Rust
+Demo	src/render/graphviz_render.rs	/^    struct Demo {$/;"	struct	line:140	language:Rust	module:tests
+render	src/render/graphviz_render.rs	/^    pub fn render(classes: &Vec<ClassInfo>, parse_option: &ParseOption) -> String {$/;"	method	line:35	language:Rust	implementation:GraphvizRender"#;
+
+        let lines = vec![str.lines()];
+        let parser = CtagsParser::parse_str(lines);
+        let classes = parser.classes();
+
+        assert_eq!(2, classes.len());
+        assert_eq!("crate::render::graphviz_render::tests", classes[0].package);
+        assert_eq!("crate::render::graphviz_render", classes[1].package);
+        assert_eq!(1, classes[1].methods.len());
+    }
+
+    #[test]
+    pub fn should_map_kinds_of_regex_language() {
+        let str = "Person\tperson.proto\t/^message Person {$/;\"\tmessage\tline:3\tlanguage:Protobuf
+name\tperson.proto\t/^  string name = 1;$/;\"\tfield\tline:4\tlanguage:Protobuf\tmessage:Person
+phones\tperson.proto\t/^  repeated PhoneNumber phones = 2;$/;\"\tfield\tline:5\tlanguage:Protobuf\tmessage:Person
+Find\tperson.proto\t/^  rpc Find(Query) returns (Person);$/;\"\trpc\tline:9\tlanguage:Protobuf\tservice:People
+People\tperson.proto\t/^service People {$/;\"\tservice\tline:8\tlanguage:Protobuf";
+        let mut option = ParseOption::default();
+        option.languages.insert(
+            "Protobuf".to_string(),
+            CtagsLanguage {
+                classes: vec!["message".to_string(), "service".to_string()],
+                members: vec!["field".to_string()],
+                methods: vec!["rpc".to_string()],
+                data_type: r"^(repeated\s+)?(?P<type>[\w.]+)\s+\w+\s*=".to_string(),
+                ..CtagsLanguage::default()
+            },
+        );
+
+        let mut parser = CtagsParser::default();
+        parser.option = option;
+        for line in str.lines() {
+            parser.parse_line(line);
+        }
+        let classes = parser.classes();
+
+        assert_eq!(2, classes.len());
+        assert_eq!("People", classes[0].name);
+        assert_eq!("service", classes[0].kind);
+        assert_eq!("Find", classes[0].methods[0].name);
+        assert_eq!("Person", classes[1].name);
+        assert_eq!("Protobuf", classes[1].lang);
+        assert_eq!("name", classes[1].members[0].name);
+        assert_eq!("string", classes[1].members[0].data_type);
+        assert_eq!("phones", classes[1].members[1].name);
+        assert_eq!("PhoneNumber", classes[1].members[1].data_type);
+    }
+
+    #[test]
+    pub fn should_own_members_tagged_before_their_class() {
+        let str = "name\tmodel.go\t/^\tname string$/;\"\tmember\tline:4\tlanguage:Go\tstruct:model.User\n\
+                   !_TAG_FILE_SORTED\t1\t/0=unsorted, 1=sorted, 2=foldcase/\n\
+                   User\tmodel.go\t/^type User struct {$/;\"\tstruct\tline:3\tlanguage:Go\tpackage:model";
+
+        let mut parser = CtagsParser::default();
+        for line in str.lines() {
+            parser.parse_line(line);
+        }
+        let classes = parser.classes();
+
+        assert_eq!(1, classes.len());
+        assert_eq!("model", classes[0].package);
+        assert_eq!("name", classes[0].members[0].name);
+        assert_eq!("string", classes[0].members[0].data_type);
+        assert_eq!(4, classes[0].members[0].line_no);
+    }
+
+    #[test]
+    pub fn should_keep_classes_of_same_name_apart_by_file_and_package() {
+        let temp = TempDir::new().unwrap();
+        for package in ["a", "b"].iter() {
+            fs::create_dir(temp.path().join(package)).unwrap();
+            fs::write(
+                temp.path().join(package).join("Order.java"),
+                format!(
+                    "package com.{};\n\nclass Order {{\n  class Item {{}}\n}}",
+                    package
+                ),
+            )
+            .unwrap();
+        }
+        let str = "id\ta/Order.java\t/^  String id;$/;\"\tfield\tline:4\tlanguage:Java\tclass:Order\n\
+                   Order\ta/Order.java\t/^class Order {$/;\"\tclass\tline:3\tlanguage:Java\n\
+                   Item\ta/Order.java\t/^  class Item {}$/;\"\tclass\tline:4\tlanguage:Java\tclass:Order\n\
+                   Order\tb/Order.java\t/^class Order {$/;\"\tclass\tline:3\tlanguage:Java\n\
+                   total\tb/Order.java\t/^  int total;$/;\"\tfield\tline:4\tlanguage:Java\tclass:Order\n\
+                   count\tb/Order.java\t/^    int count;$/;\"\tfield\tline:5\tlanguage:Java\tclass:Order.Item\n\
+                   Item\tb/Order.java\t/^  class Item {}$/;\"\tclass\tline:4\tlanguage:Java\tclass:Order";
+
+        let mut parser = CtagsParser::default();
+        for line in str.lines() {
+            parser.parse_line(line);
+        }
+        let mut classes = parser.classes();
+        read_sources(&mut classes, temp.path(), &CrateRoots::default());
+
+        assert_eq!(4, classes.len());
+        assert_eq!("com.a.Order", classes[0].package);
+        assert_eq!(
+            "Java:com.a.Order.Item@a/Order.java",
+            classes[0].stable_id(temp.path())
+        );
+        assert_eq!("com.b.Order", classes[1].package);
+        assert!(classes[0].members.is_empty());
+        assert_eq!("count", classes[1].members[0].name);
+        assert_eq!("com.a", classes[2].package);
+        assert_eq!("id", classes[2].members[0].name);
+        assert_eq!("com.b", classes[3].package);
+        assert_eq!("total", classes[3].members[0].name);
+    }
+
+    #[test]
+    pub fn should_report_dropped_tag_lines() {
+        let str = "!_TAG_FILE_SORTED\t1\t/0=unsorted, 1=sorted, 2=foldcase/
+User\tUser.java\t/^public class User {$/;\"\tclass\tline:1\tlanguage:Java
+name\tUser.java\t/^  private String name;$/;\"\tfield\tline:2\tlanguage:Java\tclass:User
+Account\taccount.py\t/^class Account:$/;\"\tclass\tline:1\tlanguage:Python
+save\taccount.py\t/^    def save(self):$/;\"\tmember\tline:2\tlanguage:Python\tclass:Account
+Repository\tRepository.java\t/^public interface Repository {$/;\"\tinterface\tline:1\tlanguage:Java
+id\tOrder.java\t/^  private long id;$/;\"\tfield\tline:2\tlanguage:Java\tclass:Order
+broken line";
+        let mut parser = CtagsParser::default();
+        parser.option.coverage_file = "modeling_coverage.json".to_string();
+        for line in str.lines() {
+            parser.parse_line(line);
+        }
+
+        let coverage = parser.coverage();
+
+        assert_eq!(7, coverage.lines);
+        assert_eq!(4, coverage.parsed);
+        let reasons: Vec<(Dropped, &str, usize)> = coverage
+            .dropped
+            .iter()
+            .map(|tags| (tags.reason, tags.kind.as_str(), tags.count))
+            .collect();
+        assert_eq!(
+            vec![
+                (Dropped::Unparsed, "", 1),
+                (Dropped::NoScope, "interface", 1),
+                (Dropped::Orphan, "field", 1),
+                (Dropped::NoDataType, "member", 1),
+            ],
+            reasons
+        );
+        assert_eq!(
+            "id\tOrder.java\t/^  private long id;$/;\"\tfield\tline:2\tlanguage:Java\tclass:Order",
+            coverage.dropped[2].samples[0]
+        );
+    }
+
     #[test]
     pub fn should_parse_golang_file() {
         let dir = tags_dir().join("go_tags");
@@ -525,8 +1187,10 @@ MethodIdentifier	SubscriberRegistry.java	/^  private static final class MethodId
 
         assert_eq!(2, classes[0].methods.len());
 
-        let mut option = ParseOption::default();
-        option.merge_method_name = true;
+        let option = ParseOption {
+            merge_method_name: true,
+            ..Default::default()
+        };
 
         let mut parser = CtagsParser::parse_str(lines);
         parser.option = option;
@@ -534,4 +1198,79 @@ MethodIdentifier	SubscriberRegistry.java	/^  private static final class MethodId
 
         assert_eq!(1, classes[0].methods.len());
     }
+
+    #[test]
+    pub fn should_identify_overloads_by_parameter_types() {
+        let str = "\
+Repository	Repository.java	/^public class Repository {$/;\"	class	line:1	language:Java
+save	Repository.java	/^    public void save(User user) {$/;\"	method	line:2	language:Java	class:Repository	access:public
+save	Repository.java	/^    public void save(List<User> users) {$/;\"	method	line:3	language:Java	class:Repository	access:public
+SAVE	Repository.java	/^    public void SAVE(final User item) {$/;\"	method	line:4	language:Java	class:Repository	access:public
+save	Repository.java	/^    public void save(User other) {$/;\"	method	line:5	language:Java	class:Repository	access:public";
+
+        let option = ParseOption {
+            merge_method_name: true,
+            ..Default::default()
+        };
+        let mut parser = CtagsParser::parse_str(vec![str.lines()]);
+        parser.option = option;
+        let methods = parser.classes()[0].methods.clone();
+
+        assert_eq!(3, methods.len());
+        assert_eq!("SAVE", methods[0].name);
+        assert_eq!("save", methods[1].name);
+        assert_eq!("save", methods[2].name);
+
+        let option = ParseOption {
+            merge_overloads: true,
+            ..Default::default()
+        };
+        let mut parser = CtagsParser::parse_str(vec![str.lines()]);
+        parser.option = option;
+        let methods = parser.classes()[0].methods.clone();
+
+        assert_eq!(2, methods.len());
+        assert_eq!(1, methods[0].overloads);
+        assert_eq!("save", methods[1].name);
+        assert_eq!(2, methods[1].overloads);
+    }
+
+    #[test]
+    fn should_get_parameter_types_by_language() {
+        assert_eq!(
+            "List<User>",
+            CtagsParser::parameter_type("Java", " final List<User> users")
+        );
+        assert_eq!(
+            "&ParseOption",
+            CtagsParser::parameter_type("Rust", "option: &ParseOption")
+        );
+        assert_eq!(
+            "(a: A) => void",
+            CtagsParser::parameter_type("TypeScript", "cb: (a: A) => void")
+        );
+        assert_eq!(
+            "number",
+            CtagsParser::parameter_type("TypeScript", "size: number = 10")
+        );
+        assert_eq!(
+            "[]string",
+            CtagsParser::parameter_type("Go", "names []string")
+        );
+    }
+
+    #[test]
+    fn should_give_grouped_go_parameters_their_type() {
+        let parameters = CtagsParser::split_parameters("a, b int, names ...string");
+        assert_eq!(
+            vec!["int", "int", "...string"],
+            CtagsParser::parameter_types("Go", &parameters)
+        );
+
+        let parameters = CtagsParser::split_parameters("int, string");
+        assert_eq!(
+            vec!["int", "string"],
+            CtagsParser::parameter_types("Go", &parameters)
+        );
+    }
 }
@@ -0,0 +1,466 @@
+/// MIT License
+//
+// Copyright (c) 2018 dalance <dalance@gmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+// https://github.com/dalance/ptags
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::str;
+use std::thread;
+
+use failure::{bail, Error, Fail, ResultExt};
+
+use crate::Opt;
+
+// ---------------------------------------------------------------------------------------------------------------------
+// Error
+// ---------------------------------------------------------------------------------------------------------------------
+#[derive(Debug, Fail)]
+enum GitError {
+    #[fail(display = "failed to execute git command ({})\n{}", cmd, err)]
+    Exit { cmd: String, err: String },
+
+    #[fail(display = "failed to call git command ({})", cmd)]
+    Spawn { cmd: String },
+
+    #[fail(display = "failed to convert to UTF-8 ({:?})", s)]
+    Utf8 { s: Vec<u8> },
+
+    #[fail(display = "failed to read git object ({})", object)]
+    MissingObject { object: String },
+}
+
+/// A file of a git tree, listed by `git ls-tree`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeEntry {
+    /// path relative to `opt.dir`
+    pub path: String,
+    pub object: String,
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// CmdGit
+// ---------------------------------------------------------------------------------------------------------------------
+pub struct CmdGit;
+
+impl CmdGit {
+    /// Returns files listed by `git ls-files` in `opt.dir`, relative to `opt.dir`.
+    ///
+    /// Untracked, ignored and submodule files are included by the options of `opt`,
+    /// and files tracked by git-lfs are excluded with `opt.exclude_lfs`.
+    pub fn get_files(opt: &Opt) -> Result<Vec<String>, Error> {
+        let mut list = CmdGit::ls_files(opt)?;
+        if opt.exclude_lfs {
+            let lfs_list = CmdGit::lfs_ls_files(opt)?;
+            list.retain(|file| lfs_list.binary_search(file).is_err());
+        }
+        Ok(list)
+    }
+
+    fn call(opt: &Opt, args: &[String]) -> Result<Output, Error> {
+        let cmd = CmdGit::get_cmd(opt, args);
+        if opt.verbose {
+            eprintln!("Call : {}", cmd);
+        }
+
+        let output = Command::new(&opt.bin_git)
+            .args(args)
+            .current_dir(&opt.dir)
+            .output()
+            .context(GitError::Spawn { cmd: cmd.clone() })?;
+
+        if !output.status.success() {
+            bail!(GitError::Exit {
+                cmd,
+                err: String::from(str::from_utf8(&output.stderr).context(GitError::Utf8 {
+                    s: output.stderr.to_vec(),
+                })?)
+            });
+        }
+
+        Ok(output)
+    }
+
+    fn ls_files(opt: &Opt) -> Result<Vec<String>, Error> {
+        let mut list = vec![];
+        let mut tracked = vec![String::from("--cached")];
+        if opt.include_submodule {
+            tracked.push(String::from("--recurse-submodules"));
+        }
+        list.append(&mut CmdGit::ls_files_by(opt, tracked)?);
+
+        // `--ignored` lists only ignored files, so untracked and ignored ones are listed apart
+        if opt.include_untracked {
+            let untracked = vec![String::from("--others"), String::from("--exclude-standard")];
+            list.append(&mut CmdGit::ls_files_by(opt, untracked)?);
+        }
+        if opt.include_ignored {
+            let ignored = vec![
+                String::from("--others"),
+                String::from("--ignored"),
+                String::from("--exclude-standard"),
+            ];
+            list.append(&mut CmdGit::ls_files_by(opt, ignored)?);
+        }
+
+        list.sort();
+        list.dedup();
+
+        if opt.verbose {
+            eprintln!("Files: {}", list.len());
+        }
+
+        Ok(list)
+    }
+
+    fn ls_files_by(opt: &Opt, mut options: Vec<String>) -> Result<Vec<String>, Error> {
+        // `-z` keeps paths with special characters unquoted, like `ls-tree`
+        let mut args = vec![String::from("ls-files"), String::from("-z")];
+        args.append(&mut options);
+        args.append(&mut opt.opt_git.clone());
+
+        let output = CmdGit::call(opt, &args)?;
+        Ok(CmdGit::to_str(&output.stdout)?
+            .split('\0')
+            .filter(|path| !path.is_empty())
+            .map(String::from)
+            .collect())
+    }
+
+    fn lfs_ls_files(opt: &Opt) -> Result<Vec<String>, Error> {
+        let mut args = vec![String::from("lfs"), String::from("ls-files")];
+        args.append(&mut opt.opt_git_lfs.clone());
+
+        let output = CmdGit::call(opt, &args)?;
+        let cdup = CmdGit::show_cdup(opt)?;
+        let prefix = CmdGit::show_prefix(opt)?;
+
+        Ok(CmdGit::parse_lfs_files(
+            CmdGit::to_str(&output.stdout)?,
+            &cdup,
+            &prefix,
+        ))
+    }
+
+    /// `git lfs ls-files` lists paths from the top of repository, like `3f4e1a2b7c * assets/logo.png`,
+    /// which are converted to be relative to the current directory like `git ls-files`.
+    fn parse_lfs_files(output: &str, cdup: &str, prefix: &str) -> Vec<String> {
+        let mut list: Vec<String> = output
+            .lines()
+            .filter_map(|line| line.splitn(3, ' ').nth(2))
+            .map(|path| match path.strip_prefix(prefix) {
+                Some(path) if !prefix.is_empty() => path.to_string(),
+                _ => format!("{}{}", cdup, path),
+            })
+            .collect();
+        list.sort();
+        list
+    }
+
+    fn show_cdup(opt: &Opt) -> Result<String, Error> {
+        let args = vec![String::from("rev-parse"), String::from("--show-cdup")];
+        let output = CmdGit::call(opt, &args)?;
+        Ok(CmdGit::to_str(&output.stdout)?.trim().to_string())
+    }
+
+    fn show_prefix(opt: &Opt) -> Result<String, Error> {
+        let args = vec![String::from("rev-parse"), String::from("--show-prefix")];
+        let output = CmdGit::call(opt, &args)?;
+        Ok(CmdGit::to_str(&output.stdout)?.trim().to_string())
+    }
+
+    /// Returns regular files of `rev` under `opt.dir`, like `v0.6.2` or `HEAD~3:src`.
+    pub fn ls_tree(opt: &Opt, rev: &str) -> Result<Vec<TreeEntry>, Error> {
+        let args = vec![
+            String::from("ls-tree"),
+            String::from("-r"),
+            String::from("-z"),
+            String::from(rev),
+        ];
+        let output = CmdGit::call(opt, &args)?;
+        Ok(CmdGit::parse_tree(CmdGit::to_str(&output.stdout)?))
+    }
+
+    /// `git ls-tree -z` lists `<mode> <type> <object>\t<path>` separated by NUL,
+    /// symlinks and submodules are skipped.
+    fn parse_tree(output: &str) -> Vec<TreeEntry> {
+        let mut entries = vec![];
+        for line in output.split('\0') {
+            let (info, path) = match line.split_once('\t') {
+                Some(item) => item,
+                None => continue,
+            };
+
+            let info: Vec<&str> = info.split(' ').collect();
+            if info.len() != 3 || info[1] != "blob" || info[0] == "120000" {
+                continue;
+            }
+
+            entries.push(TreeEntry {
+                path: path.to_string(),
+                object: info[2].to_string(),
+            });
+        }
+        entries
+    }
+
+    /// Writes the blobs of `entries` into `target` by `git cat-file --batch`, so files of a
+    /// revision are read without checking it out.
+    pub fn write_blobs(opt: &Opt, entries: &[TreeEntry], target: &Path) -> Result<(), Error> {
+        let args = vec![String::from("cat-file"), String::from("--batch")];
+        let cmd = CmdGit::get_cmd(opt, &args);
+        if opt.verbose {
+            eprintln!("Call : {}", cmd);
+        }
+
+        let mut child = Command::new(&opt.bin_git)
+            .args(&args)
+            .current_dir(&opt.dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context(GitError::Spawn { cmd: cmd.clone() })?;
+
+        // git answers while reading, so objects are requested in another thread to not block
+        let mut stdin = child.stdin.take().unwrap();
+        let objects: Vec<String> = entries.iter().map(|e| format!("{}\n", e.object)).collect();
+        let writer = thread::spawn(move || stdin.write_all(objects.concat().as_bytes()));
+
+        let mut output = vec![];
+        child.stdout.take().unwrap().read_to_end(&mut output)?;
+        let _ = writer.join();
+        child.wait()?;
+
+        let mut rest = output.as_slice();
+        for entry in entries {
+            let (content, next) =
+                CmdGit::next_blob(rest).ok_or_else(|| GitError::MissingObject {
+                    object: entry.object.clone(),
+                })?;
+
+            let path = target.join(&entry.path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, content)?;
+            rest = next;
+        }
+
+        Ok(())
+    }
+
+    /// Splits `<object> <type> <size>\n<content>\n` from the output of `git cat-file --batch`.
+    fn next_blob(output: &[u8]) -> Option<(&[u8], &[u8])> {
+        let header_end = output.iter().position(|b| *b == b'\n')?;
+        let header = str::from_utf8(&output[..header_end]).ok()?;
+        let size: usize = header.split(' ').nth(2)?.parse().ok()?;
+
+        let start = header_end + 1;
+        let end = start + size;
+        if output.len() < end + 1 {
+            return None;
+        }
+        Some((&output[start..end], &output[end + 1..]))
+    }
+
+    fn to_str(bytes: &[u8]) -> Result<&str, Error> {
+        Ok(str::from_utf8(bytes).context(GitError::Utf8 { s: bytes.to_vec() })?)
+    }
+
+    fn get_cmd(opt: &Opt, args: &[String]) -> String {
+        let mut cmd = format!(
+            "cd {}; {}",
+            opt.dir.to_string_lossy(),
+            opt.bin_git.to_string_lossy()
+        );
+        for arg in args {
+            cmd = format!("{} {}", cmd, arg);
+        }
+        cmd
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// Test
+// ---------------------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::process::Command;
+
+    use structopt::StructOpt;
+    use tempfile::TempDir;
+
+    use crate::Opt;
+
+    use super::{CmdGit, TreeEntry};
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    fn repo() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src").join("main.rs"), "struct Demo;").unwrap();
+        fs::write(dir.path().join("untracked.rs"), "struct Untracked;").unwrap();
+        fs::write(dir.path().join("ignored.rs"), "struct Ignored;").unwrap();
+        fs::write(dir.path().join(".gitignore"), "ignored.rs\n").unwrap();
+        git(dir.path(), &["add", "src/main.rs", ".gitignore"]);
+        dir
+    }
+
+    fn opt(dir: &Path, flags: &[&str]) -> Opt {
+        let mut args = vec!["ptags"];
+        args.extend(flags);
+        let mut opt = Opt::from_iter(args.iter());
+        opt.dir = dir.to_path_buf();
+        opt
+    }
+
+    #[test]
+    fn should_list_tracked_files() {
+        let repo = repo();
+        let files = CmdGit::get_files(&opt(repo.path(), &[])).unwrap();
+        assert_eq!(vec![".gitignore", "src/main.rs"], files);
+    }
+
+    #[test]
+    fn should_include_untracked_and_ignored_files() {
+        let repo = repo();
+        let files = CmdGit::get_files(&opt(repo.path(), &["--include-untracked"])).unwrap();
+        assert_eq!(vec![".gitignore", "src/main.rs", "untracked.rs"], files);
+
+        let files = CmdGit::get_files(&opt(repo.path(), &["--include-ignored"])).unwrap();
+        assert_eq!(vec![".gitignore", "ignored.rs", "src/main.rs"], files);
+    }
+
+    #[test]
+    fn should_list_files_with_special_characters_unquoted() {
+        let repo = repo();
+        fs::write(
+            repo.path().join("src").join("café order.rs"),
+            "struct Order;",
+        )
+        .unwrap();
+        git(repo.path(), &["add", "src"]);
+
+        let files = CmdGit::get_files(&opt(repo.path(), &[])).unwrap();
+        assert_eq!(
+            vec![".gitignore", "src/café order.rs", "src/main.rs"],
+            files
+        );
+    }
+
+    #[test]
+    fn should_list_files_relative_to_sub_dir() {
+        let repo = repo();
+        let files = CmdGit::get_files(&opt(&repo.path().join("src"), &[])).unwrap();
+        assert_eq!(vec!["main.rs"], files);
+    }
+
+    #[test]
+    fn should_fail_outside_of_repository() {
+        let dir = TempDir::new().unwrap();
+        assert!(CmdGit::get_files(&opt(dir.path(), &[])).is_err());
+    }
+
+    fn commit(dir: &Path, message: &str) {
+        git(
+            dir,
+            &[
+                "-c",
+                "user.name=modeling",
+                "-c",
+                "user.email=modeling@inherd.org",
+                "commit",
+                "-q",
+                "-m",
+                message,
+            ],
+        );
+    }
+
+    #[test]
+    fn should_write_files_of_revision() {
+        let repo = repo();
+        commit(repo.path(), "first");
+        git(repo.path(), &["tag", "v1"]);
+
+        fs::write(repo.path().join("src").join("main.rs"), "struct Changed;").unwrap();
+        git(repo.path(), &["add", "src/main.rs"]);
+        commit(repo.path(), "second");
+
+        let opt = opt(repo.path(), &[]);
+        let entries = CmdGit::ls_tree(&opt, "v1").unwrap();
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(vec![".gitignore", "src/main.rs"], paths);
+
+        let target = TempDir::new().unwrap();
+        CmdGit::write_blobs(&opt, &entries, target.path()).unwrap();
+        let content = fs::read_to_string(target.path().join("src").join("main.rs")).unwrap();
+        assert_eq!("struct Demo;", content);
+    }
+
+    #[test]
+    fn should_fail_with_unknown_revision() {
+        let repo = repo();
+        commit(repo.path(), "first");
+        assert!(CmdGit::ls_tree(&opt(repo.path(), &[]), "not-a-rev").is_err());
+    }
+
+    #[test]
+    fn should_skip_symlink_and_submodule_in_tree() {
+        let output =
+            "100644 blob 1a2b\tsrc/main.rs\x00120000 blob 3c4d\tlink\x00160000 commit 5e6f\tvendor\x00";
+        assert_eq!(
+            vec![TreeEntry {
+                path: "src/main.rs".to_string(),
+                object: "1a2b".to_string(),
+            }],
+            CmdGit::parse_tree(output)
+        );
+    }
+
+    #[test]
+    fn should_convert_lfs_files_to_current_dir() {
+        let output = "3f4e1a2b7c * assets/logo.png\n9c8b7a6d5e - src/big file.bin\n";
+
+        assert_eq!(
+            vec!["assets/logo.png", "src/big file.bin"],
+            CmdGit::parse_lfs_files(output, "", "")
+        );
+        assert_eq!(
+            vec!["../assets/logo.png", "big file.bin"],
+            CmdGit::parse_lfs_files(output, "../", "src/")
+        );
+    }
+}
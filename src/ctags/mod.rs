@@ -1,3 +1,9 @@
+pub mod access;
+pub mod ctags_cache;
 pub mod ctags_cmd;
+pub mod ctags_coverage;
+pub mod ctags_lang;
 pub mod ctags_opt;
 pub mod ctags_parser;
+pub mod git_cmd;
+pub mod rust_module;
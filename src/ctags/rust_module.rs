@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Crate roots of Rust files, which are the nearest directories containing `Cargo.toml`. They're
+/// found before parsing, each directory is checked once.
+#[derive(Debug, Clone, Default)]
+pub struct CrateRoots {
+    /// crate root of each directory of files, `None` if it's not in a crate
+    roots: HashMap<PathBuf, Option<PathBuf>>,
+}
+
+impl CrateRoots {
+    /// Finds crate roots of `.rs` files in `files`, which are relative to `dir` like files passed
+    /// to ctags.
+    pub fn find<P: AsRef<Path>>(dir: P, files: &[String]) -> CrateRoots {
+        let mut crate_roots = CrateRoots::default();
+        for file in files.iter().filter(|file| file.ends_with(".rs")) {
+            if let Some(parent) = Path::new(file).parent() {
+                crate_roots.root_of(dir.as_ref(), parent);
+            }
+        }
+        crate_roots
+    }
+
+    fn root_of(&mut self, dir: &Path, path: &Path) -> Option<PathBuf> {
+        if let Some(root) = self.roots.get(path) {
+            return root.clone();
+        }
+
+        let root = if dir.join(path).join("Cargo.toml").is_file() {
+            Some(path.to_path_buf())
+        } else {
+            path.parent().and_then(|parent| self.root_of(dir, parent))
+        };
+        self.roots.insert(path.to_path_buf(), root.clone());
+        root
+    }
+
+    /// Returns the Rust module path of `file` like `module_path`, but relative to its crate root
+    /// if it was found.
+    pub fn module_path(&self, file: &str) -> String {
+        let path = Path::new(file);
+        let root = path
+            .parent()
+            .and_then(|parent| self.roots.get(parent))
+            .and_then(|root| root.as_ref());
+        match root.and_then(|root| path.strip_prefix(root).ok()) {
+            Some(relative) => modules_of(&components_of(relative)),
+            None => module_path(file),
+        }
+    }
+}
+
+/// Returns the Rust module path of a source file, like `crate::render::graphviz_render`.
+///
+/// The crate root is the last `src` directory of the path, use `CrateRoots` to find it by
+/// `Cargo.toml`. Binaries, examples, tests and benches are their own crate roots and use their
+/// file name.
+pub fn module_path(file: &str) -> String {
+    let components = components_of(Path::new(file));
+    match components.iter().rposition(|c| c == "src") {
+        Some(index) => modules_of(&components[index..]),
+        None => modules_of(&components),
+    }
+}
+
+/// Returns the module path of `components`, which are relative to the crate root.
+fn modules_of(components: &[String]) -> String {
+    if components.is_empty() {
+        return "".to_string();
+    }
+
+    let mut modules: Vec<String> = vec![];
+    let mut rest = components;
+    match rest[0].as_str() {
+        "src" if rest.len() > 2 && rest[1] == "bin" => {
+            modules.push(strip_rs(&rest[2]));
+            rest = &rest[3..];
+        }
+        "examples" | "tests" | "benches" if rest.len() > 1 => {
+            modules.push(strip_rs(&rest[1]));
+            rest = &rest[2..];
+        }
+        "src" => {
+            modules.push("crate".to_string());
+            rest = &rest[1..];
+        }
+        _ => {
+            modules.push("crate".to_string());
+        }
+    }
+
+    for (index, name) in rest.iter().enumerate() {
+        let is_file = index == rest.len() - 1;
+        if is_file && (name == "mod.rs" || name == "lib.rs" || name == "main.rs") {
+            continue;
+        }
+
+        modules.push(strip_rs(name));
+    }
+
+    modules.join("::")
+}
+
+/// Appends the inline `mod` scope reported by ctags (`module:tests`) to the file's module path.
+pub fn with_inline_module(module_path: &str, inline_module: &str) -> String {
+    if inline_module.is_empty() {
+        return module_path.to_string();
+    }
+
+    if module_path.is_empty() {
+        return inline_module.to_string();
+    }
+
+    format!("{}::{}", module_path, inline_module)
+}
+
+fn components_of(path: &Path) -> Vec<String> {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => s.to_str().map(|s| s.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn strip_rs(name: &str) -> String {
+    name.strip_suffix(".rs").unwrap_or(name).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use crate::ctags::rust_module::{module_path, with_inline_module, CrateRoots};
+
+    #[test]
+    fn should_build_module_path_from_file() {
+        assert_eq!(
+            "crate::render::graphviz_render",
+            module_path("src/render/graphviz_render.rs")
+        );
+        assert_eq!("crate::render", module_path("src/render/mod.rs"));
+        assert_eq!("crate", module_path("coco_swagger/src/lib.rs"));
+    }
+
+    #[test]
+    fn should_use_file_name_for_binary_crates() {
+        assert_eq!("modeling", module_path("src/bin/modeling.rs"));
+        assert_eq!("cli", module_path("tests/cli.rs"));
+    }
+
+    #[test]
+    fn should_find_crate_root_in_dir() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("shop").join("build")).unwrap();
+        fs::write(dir.join("shop").join("Cargo.toml"), "[package]").unwrap();
+        let file = "shop/build/generated.rs";
+
+        let roots = CrateRoots::find(dir, &[file.to_string()]);
+
+        assert_eq!("crate::build::generated", roots.module_path(file));
+        assert_eq!("crate::shop::build::generated", module_path(file));
+        assert_eq!("crate::render", roots.module_path("src/render/mod.rs"));
+    }
+
+    #[test]
+    fn should_append_inline_module() {
+        assert_eq!(
+            "crate::render::tests",
+            with_inline_module("crate::render", "tests")
+        );
+        assert_eq!("crate::render", with_inline_module("crate::render", ""));
+    }
+}
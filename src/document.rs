@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+use failure::{bail, Error};
+use serde::{Deserialize, Serialize};
+
+use crate::ctags::ctags_coverage::Coverage;
+use crate::ClassInfo;
+
+/// version of the model document, increased on changes which break its readers
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// JSON Schema of the model document
+pub const SCHEMA: &str = include_str!("../schema/model.schema.json");
+
+/// Model of source code for other tools, see `schema/model.schema.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModelDocument {
+    pub schema_version: u32,
+    /// version of modeling which exported the model
+    pub tool_version: String,
+    /// dir of the source code, files of classes are relative to it
+    pub source_root: String,
+    pub classes: Vec<ClassInfo>,
+    /// relations between classes of the model, by ids of classes
+    pub relations: Vec<Relation>,
+    /// coverage of tag lines with `--report-coverage`, otherwise null
+    #[serde(default)]
+    pub diagnostics: Option<Coverage>,
+}
+
+/// A relation from `source` to `target` class, which are ids of classes.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Relation {
+    pub source: String,
+    pub target: String,
+    pub kind: RelationKind,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationKind {
+    /// `target` is a parent of `source`
+    Inheritance,
+    /// a member of `source` is typed by `target`
+    Composition,
+    /// a method of `source` returns `target`
+    Dependency,
+}
+
+impl ModelDocument {
+    pub fn new(source_root: &str, classes: Vec<ClassInfo>) -> ModelDocument {
+        ModelDocument {
+            schema_version: SCHEMA_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            source_root: source_root.to_string(),
+            relations: relations(&classes),
+            classes,
+            diagnostics: None,
+        }
+    }
+
+    /// Returns the document in `content`, documents of a newer schema version are refused.
+    pub fn from_json(content: &str) -> Result<ModelDocument, Error> {
+        let document: ModelDocument = serde_json::from_str(content)?;
+        if document.schema_version > SCHEMA_VERSION {
+            bail!(
+                "unsupported schema version {} of model, supported up to {}",
+                document.schema_version,
+                SCHEMA_VERSION
+            );
+        }
+        Ok(document)
+    }
+}
+
+/// Returns classes in `content`, which is a model document, or classes saved by `model --debug`.
+pub fn read_classes(content: &str) -> Result<Vec<ClassInfo>, Error> {
+    if content.trim_start().starts_with('[') {
+        return Ok(serde_json::from_str(content)?);
+    }
+    Ok(ModelDocument::from_json(content)?.classes)
+}
+
+/// Returns relations between classes by their ids, like `ModuleGraph` but for classes. Parents
+/// are not resolved, so they are found by the name if it's unique.
+pub fn relations(classes: &[ClassInfo]) -> Vec<Relation> {
+    let mut by_qualified: HashMap<String, String> = HashMap::new();
+    let mut by_name: HashMap<&str, Vec<String>> = HashMap::new();
+    for clazz in classes {
+        by_qualified.insert(clazz.qualified_name(), clazz.id.clone());
+        by_name
+            .entry(&clazz.name)
+            .or_default()
+            .push(clazz.id.clone());
+    }
+
+    let mut relations = vec![];
+    for clazz in classes {
+        let source = &clazz.id;
+        let mut push = |target: Option<&String>, kind: RelationKind| {
+            if let Some(target) = target {
+                if target != source {
+                    relations.push(Relation {
+                        source: source.clone(),
+                        target: target.clone(),
+                        kind,
+                    });
+                }
+            }
+        };
+
+        for parent in &clazz.parents {
+            let target = by_qualified.get(parent).or_else(|| {
+                match by_name.get(parent.as_str()).map(|ids| ids.as_slice()) {
+                    Some([id]) => Some(id),
+                    _ => None,
+                }
+            });
+            push(target, RelationKind::Inheritance);
+        }
+        for member in clazz.members.iter().filter(|member| !member.external) {
+            push(
+                by_qualified.get(&member.qualified_type),
+                RelationKind::Composition,
+            );
+        }
+        for method in clazz.methods.iter().filter(|method| !method.external) {
+            push(
+                by_qualified.get(&method.qualified_return_type),
+                RelationKind::Dependency,
+            );
+        }
+    }
+
+    relations.sort();
+    relations.dedup();
+    relations
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde_json::Value;
+
+    use crate::coco_struct::{ClassInfo, MemberInfo, MethodInfo};
+    use crate::ctags::ctags_coverage::Coverage;
+    use crate::document::{read_classes, ModelDocument, RelationKind, SCHEMA};
+
+    fn document() -> ModelDocument {
+        let mut order = ClassInfo::new("Order");
+        order.package = "shop".to_string();
+        order.lang = "Java".to_string();
+        order.file = "src/shop/Order.java".to_string();
+        order.parents.push("Entity".to_string());
+        let mut member = MemberInfo::new("items", "-".to_string(), "Item".to_string());
+        member.qualified_type = "shop.Item".to_string();
+        order.members.push(member);
+        let mut method = MethodInfo::new("total", "+".to_string(), vec![], "Money".to_string());
+        method.external = true;
+        order.methods.push(method);
+
+        let mut item = ClassInfo::new("Item");
+        item.package = "shop".to_string();
+        item.lang = "Java".to_string();
+        let mut entity = ClassInfo::new("Entity");
+        entity.package = "base".to_string();
+        entity.lang = "Java".to_string();
+
+        let mut classes = vec![order, item, entity];
+        for clazz in classes.iter_mut() {
+            clazz.id = clazz.stable_id(Path::new(""));
+        }
+        let mut document = ModelDocument::new("/code/shop", classes);
+        document.diagnostics = Some(Coverage::default());
+        document
+    }
+
+    #[test]
+    fn should_relate_classes_in_model() {
+        let relations = document().relations;
+
+        assert_eq!(2, relations.len());
+        assert_eq!("Java:shop.Order@src/shop/Order.java", relations[0].source);
+        assert_eq!("Java:base.Entity@", relations[0].target);
+        assert_eq!(RelationKind::Inheritance, relations[0].kind);
+        assert_eq!("Java:shop.Item@", relations[1].target);
+        assert_eq!(RelationKind::Composition, relations[1].kind);
+    }
+
+    #[test]
+    fn should_round_trip_document() {
+        let json = serde_json::to_string(&document()).unwrap();
+
+        let document = ModelDocument::from_json(&json).unwrap();
+
+        assert_eq!(json, serde_json::to_string(&document).unwrap());
+        assert_eq!(3, read_classes(&json).unwrap().len());
+    }
+
+    #[test]
+    fn should_read_classes_saved_by_debug() {
+        let json = serde_json::to_string(&document().classes).unwrap();
+
+        assert_eq!(3, read_classes(&json).unwrap().len());
+    }
+
+    #[test]
+    fn should_read_classes_saved_without_ids() {
+        let json = r#"[{"id":0,"name":"Order","package":"shop","file":"Order.java","lang":"Java","parents":[],"members":[],"methods":[]}]"#;
+
+        let classes = read_classes(json).unwrap();
+
+        assert_eq!("", classes[0].id);
+    }
+
+    #[test]
+    fn should_refuse_newer_schema_version() {
+        let mut document = document();
+        document.schema_version += 1;
+        let json = serde_json::to_string(&document).unwrap();
+
+        assert!(ModelDocument::from_json(&json).is_err());
+    }
+
+    /// Checks the fields of the document against `properties` and `required` of the schema.
+    fn check_schema(schema: &Value, definition: &Value, value: &Value, path: &str) {
+        let definition = match definition["$ref"].as_str() {
+            Some(reference) => {
+                &schema["definitions"][reference.trim_start_matches("#/definitions/")]
+            }
+            None => definition,
+        };
+
+        if let Some(items) = value.as_array() {
+            for item in items {
+                check_schema(schema, &definition["items"], item, path);
+            }
+            return;
+        }
+        let object = match value.as_object() {
+            Some(object) => object,
+            None => return,
+        };
+
+        let properties = definition["properties"].as_object().unwrap();
+        for (key, value) in object {
+            let property = properties
+                .get(key)
+                .unwrap_or_else(|| panic!("{}.{} is not in schema", path, key));
+            check_schema(schema, property, value, &format!("{}.{}", path, key));
+        }
+        for key in definition["required"].as_array().unwrap() {
+            let key = key.as_str().unwrap();
+            assert!(object.contains_key(key), "{}.{} is required", path, key);
+        }
+    }
+
+    #[test]
+    fn should_match_schema() {
+        let schema: Value = serde_json::from_str(SCHEMA).unwrap();
+        let value = serde_json::to_value(document()).unwrap();
+
+        check_schema(&schema, &schema, &value, "model");
+        assert_eq!(
+            value["schema_version"],
+            schema["properties"]["schema_version"]["const"]
+        );
+    }
+}
@@ -1,60 +1,235 @@
+use globset::{Glob, GlobMatcher, GlobSet, GlobSetBuilder};
 use grep_regex::RegexMatcher;
 use grep_searcher::sinks::UTF8;
 use grep_searcher::Searcher;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
+/// Filters files by grep, packages, suffixes and globs, a file is allowed only when it matches
+/// all of the given criteria.
+///
+/// Regexes and globs are compiled when they're set, so a filter can be shared by threads.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(from = "FilterConfig")]
 pub struct FileFilter {
     grep: String,
     packages: Vec<String>,
     suffixes: Vec<String>,
+    /// globs of files to include, like `src/**`; `!**/generated/**` excludes instead
+    includes: Vec<String>,
+    /// globs of files to exclude, like `**/test/**` or `*Test.java`; the last matched pattern
+    /// wins, so `!pattern` includes files back like `.gitignore`
+    excludes: Vec<String>,
+    /// regex searched in the content of files, like `@Entity` or `implements Serializable`
+    content: String,
+    /// compiled `grep`, `None` if it's empty or invalid
+    #[serde(skip)]
+    grep_matcher: Option<RegexMatcher>,
+    /// compiled `includes` and `excludes`, `None` for invalid patterns
+    #[serde(skip)]
+    globs: Option<PathGlobs>,
+    /// compiled `content`, `None` if it's empty or invalid
+    #[serde(skip)]
+    content_matcher: Option<RegexMatcher>,
+}
+
+/// Options of `FileFilter` in configs, compiled into a filter after deserializing.
+#[derive(Deserialize)]
+struct FilterConfig {
+    grep: String,
+    packages: Vec<String>,
+    suffixes: Vec<String>,
+    #[serde(default)]
+    includes: Vec<String>,
+    #[serde(default)]
+    excludes: Vec<String>,
+    #[serde(default)]
+    content: String,
+}
+
+impl From<FilterConfig> for FileFilter {
+    fn from(config: FilterConfig) -> Self {
+        FileFilter::new(config.packages, config.suffixes, config.grep)
+            .with_includes(config.includes)
+            .with_excludes(config.excludes)
+            .with_content(config.content)
+    }
 }
 
 impl Default for FileFilter {
     fn default() -> Self {
-        FileFilter {
-            grep: "".to_string(),
-            packages: vec![],
-            suffixes: vec![],
-        }
+        FileFilter::new(vec![], vec![], "".to_string())
     }
 }
 
 impl FileFilter {
     pub fn new(packages: Vec<String>, suffixes: Vec<String>, string: String) -> FileFilter {
         FileFilter {
+            grep_matcher: compile_regex(&string),
             grep: string,
             packages,
             suffixes,
+            includes: vec![],
+            excludes: vec![],
+            content: "".to_string(),
+            globs: compile_globs(&[], &[]),
+            content_matcher: None,
         }
     }
 
+    pub fn with_includes(mut self, includes: Vec<String>) -> FileFilter {
+        self.includes = includes;
+        self.globs = compile_globs(&self.includes, &self.excludes);
+        self
+    }
+
+    pub fn with_excludes(mut self, excludes: Vec<String>) -> FileFilter {
+        self.excludes = excludes;
+        self.globs = compile_globs(&self.includes, &self.excludes);
+        self
+    }
+
+    pub fn with_content(mut self, content: String) -> FileFilter {
+        self.content_matcher = compile_regex(&content);
+        self.content = content;
+        self
+    }
+
+    /// Returns whether the file matches both the path and the content criteria.
     pub fn allow(&self, path: PathBuf) -> bool {
+        self.allow_path(&path) && self.allow_content(&path)
+    }
+
+    /// Checks grep, packages, suffixes and globs against the path of file.
+    pub fn allow_path(&self, path: &Path) -> bool {
+        self.allow_path_in(Path::new(""), path)
+    }
+
+    /// Checks the path like `allow_path`, but globs match the path relative to `root`, the dir
+    /// which files are walked from, like `main/**` for `src/main/Order.java` walked from `src`.
+    pub fn allow_path_in(&self, root: &Path, path: &Path) -> bool {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let path = path.to_path_buf();
         if self.grep.len() > 0 {
-            return match RegexMatcher::new(&self.grep) {
-                Ok(matcher) => grep_by_text(&matcher, &format!("{:}", path.display())),
-                Err(err) => {
-                    println!("error: {:?}", err);
-                    false
-                }
+            let is_match = match &self.grep_matcher {
+                Some(matcher) => grep_by_text(matcher, &format!("{:}", path.display())),
+                None => false,
             };
+            if !is_match {
+                return false;
+            }
+        }
+
+        if !filter_by_packages(path.clone(), &self.packages) {
+            return false;
+        }
+
+        if !filter_by_suffix(path.clone(), &self.suffixes) {
+            return false;
         }
 
-        if self.packages.len() == 0 && self.suffixes.len() == 0 {
+        match &self.globs {
+            Some(globs) => globs.allow(relative),
+            None => false,
+        }
+    }
+
+    /// Searches `content` regex in the file, files which can't be read are not allowed.
+    pub fn allow_content(&self, path: &Path) -> bool {
+        if self.content.is_empty() {
             return true;
         }
 
-        if self.packages.len() > 0 {
-            return filter_by_packages(path, &self.packages);
+        match &self.content_matcher {
+            Some(matcher) => grep_by_file(matcher, path),
+            None => false,
         }
+    }
+}
+
+/// Compiles a regex of `grep` or `content`, an invalid one is reported once and allows no file.
+fn compile_regex(pattern: &str) -> Option<RegexMatcher> {
+    if pattern.is_empty() {
+        return None;
+    }
 
-        if self.suffixes.len() > 0 {
-            return filter_by_suffix(path, &self.suffixes);
+    match RegexMatcher::new(pattern) {
+        Ok(matcher) => Some(matcher),
+        Err(err) => {
+            eprintln!("error: {:?}", err);
+            None
         }
+    }
+}
 
-        return false;
+fn compile_globs(includes: &[String], excludes: &[String]) -> Option<PathGlobs> {
+    match PathGlobs::new(includes, excludes) {
+        Ok(globs) => Some(globs),
+        Err(err) => {
+            eprintln!("error: {:?}", err);
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PathGlobs {
+    includes: GlobSet,
+    negated_includes: GlobSet,
+    /// matcher with whether it is negated, in the given order
+    excludes: Vec<(GlobMatcher, bool)>,
+}
+
+impl PathGlobs {
+    fn new(includes: &[String], excludes: &[String]) -> Result<PathGlobs, globset::Error> {
+        let mut include_builder = GlobSetBuilder::new();
+        let mut negated_builder = GlobSetBuilder::new();
+        for pattern in includes {
+            match pattern.strip_prefix('!') {
+                Some(negated) => negated_builder.add(Glob::new(negated)?),
+                None => include_builder.add(Glob::new(pattern)?),
+            };
+        }
+
+        let mut exclude_matchers = vec![];
+        for pattern in excludes {
+            let (glob, negated) = match pattern.strip_prefix('!') {
+                Some(negated) => (negated, true),
+                None => (pattern.as_str(), false),
+            };
+            exclude_matchers.push((Glob::new(glob)?.compile_matcher(), negated));
+        }
+
+        Ok(PathGlobs {
+            includes: include_builder.build()?,
+            negated_includes: negated_builder.build()?,
+            excludes: exclude_matchers,
+        })
+    }
+
+    fn allow(&self, path: &Path) -> bool {
+        let path = path.strip_prefix("./").unwrap_or(path);
+        let file_name = Path::new(path.file_name().unwrap_or_default());
+
+        if !self.includes.is_empty()
+            && !self.includes.is_match(path)
+            && !self.includes.is_match(file_name)
+        {
+            return false;
+        }
+
+        if self.negated_includes.is_match(path) || self.negated_includes.is_match(file_name) {
+            return false;
+        }
+
+        let mut excluded = false;
+        for (matcher, negated) in &self.excludes {
+            if matcher.is_match(path) || matcher.is_match(file_name) {
+                excluded = !negated;
+            }
+        }
+        !excluded
     }
 }
 
@@ -75,6 +250,22 @@ pub fn grep_by_text(matcher: &RegexMatcher, text: &str) -> bool {
     has_match
 }
 
+pub fn grep_by_file(matcher: &RegexMatcher, path: &Path) -> bool {
+    let mut searcher = Searcher::new();
+
+    let mut has_match = false;
+    let _ = searcher.search_path(
+        matcher,
+        path,
+        UTF8(|_, _| {
+            has_match = true;
+            Ok(false)
+        }),
+    );
+
+    has_match
+}
+
 pub fn filter_by_packages(path: PathBuf, packages: &Vec<String>) -> bool {
     if packages.len() == 0 {
         return true;
@@ -114,8 +305,115 @@ pub fn filter_by_suffix(path: PathBuf, suffixes: &Vec<String>) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use crate::file_filter::{filter_by_packages, filter_by_suffix};
-    use std::path::PathBuf;
+    use crate::file_filter::{filter_by_packages, filter_by_suffix, FileFilter};
+    use std::path::{Path, PathBuf};
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn should_combine_criteria_with_and() {
+        let filter = FileFilter::new(
+            strings(&["model"]),
+            strings(&["Model"]),
+            "Custom".to_string(),
+        );
+
+        assert!(filter.allow(PathBuf::from("src/model/CustomModel.java")));
+        assert!(!filter.allow(PathBuf::from("src/model/UserModel.java")));
+        assert!(!filter.allow(PathBuf::from("src/view/CustomModel.java")));
+        assert!(!filter.allow(PathBuf::from("src/model/CustomView.java")));
+    }
+
+    #[test]
+    fn should_exclude_by_globs() {
+        let filter =
+            FileFilter::default().with_excludes(strings(&["**/test/**", "*Test.java"]));
+
+        assert!(filter.allow(PathBuf::from("./src/main/Order.java")));
+        assert!(!filter.allow(PathBuf::from("./src/test/Order.java")));
+        assert!(!filter.allow(PathBuf::from("src/main/OrderTest.java")));
+    }
+
+    #[test]
+    fn should_include_back_by_negated_exclude() {
+        let filter = FileFilter::default()
+            .with_excludes(strings(&["**/test/**", "!**/test/fixtures/**"]));
+
+        assert!(!filter.allow(PathBuf::from("src/test/OrderTest.java")));
+        assert!(filter.allow(PathBuf::from("src/test/fixtures/Order.java")));
+    }
+
+    #[test]
+    fn should_include_by_globs() {
+        let filter = FileFilter::default()
+            .with_includes(strings(&["src/**/*.java", "!**/generated/**"]))
+            .with_excludes(strings(&["*Test.java"]));
+
+        assert!(filter.allow(PathBuf::from("src/main/Order.java")));
+        assert!(!filter.allow(PathBuf::from("src/main/Order.kt")));
+        assert!(!filter.allow(PathBuf::from("src/generated/Order.java")));
+        assert!(!filter.allow(PathBuf::from("src/main/OrderTest.java")));
+    }
+
+    #[test]
+    fn should_match_globs_relative_to_root() {
+        let filter = FileFilter::default()
+            .with_includes(strings(&["main/**"]))
+            .with_excludes(strings(&["app/**"]));
+        let root = Path::new("../shop/app");
+
+        assert!(filter.allow_path_in(root, &root.join("main").join("Order.java")));
+        assert!(!filter.allow_path_in(root, &root.join("test").join("Order.java")));
+        assert!(!filter.allow_path(&root.join("main").join("Order.java")));
+    }
+
+    #[test]
+    fn should_filter_by_content() {
+        let root_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let source = root_dir.join("_fixtures").join("ctags").join("source");
+
+        let filter = FileFilter::default().with_content("class\\s+Horse".to_string());
+        assert!(filter.allow(source.join("animal.ts")));
+        assert!(!filter.allow(source.join("datastore.go")));
+        assert!(!filter.allow(source.join("not_exists.ts")));
+    }
+
+    #[test]
+    fn should_check_path_before_content() {
+        let root_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let source = root_dir.join("_fixtures").join("ctags").join("source");
+
+        let filter = FileFilter::default()
+            .with_excludes(vec!["*.ts".to_string()])
+            .with_content("Horse".to_string());
+        assert!(!filter.allow(source.join("animal.ts")));
+    }
+
+    #[test]
+    fn should_share_filter_by_threads() {
+        fn assert_sync<T: Sync>(_: &T) {}
+
+        let filter = FileFilter::new(vec![], vec![], "Order".to_string())
+            .with_excludes(strings(&["*Test.java"]));
+        assert_sync(&filter);
+
+        let deserialized: FileFilter = serde_json::from_str(
+            r#"{"grep":"Order","packages":[],"suffixes":[],"excludes":["*Test.java"]}"#,
+        )
+        .unwrap();
+        assert!(deserialized.allow(PathBuf::from("src/Order.java")));
+        assert!(!deserialized.allow(PathBuf::from("src/OrderTest.java")));
+        assert!(!deserialized.allow(PathBuf::from("src/User.java")));
+    }
+
+    #[test]
+    fn should_not_allow_any_with_invalid_glob() {
+        let filter = FileFilter::default().with_excludes(strings(&["src/[a"]));
+
+        assert!(!filter.allow(PathBuf::from("src/main/Order.java")));
+    }
 
     #[test]
     fn should_filter_by_file_name_suffix() {
@@ -2,26 +2,50 @@
 extern crate lazy_static;
 extern crate serde;
 
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fs;
 use std::path::Path;
 
+use failure::Error;
 use ignore::Walk;
 use structopt::StructOpt;
+use tempfile::TempDir;
 
+pub use class_filter::ClassFilter;
 pub use coco_struct::{ClassInfo, MemberInfo, MethodInfo};
-pub use ctags::ctags_cmd::CmdCtags;
+pub use ctags::ctags_cmd::{CmdCtags, CtagsVersion};
 pub use ctags::ctags_opt::Opt;
 pub use ctags::ctags_parser::CtagsParser;
+pub use ctags::git_cmd::{CmdGit, TreeEntry};
 pub use file_filter::*;
 pub use parse_option::ParseOption;
+pub use source::dependency::DependencyGraph;
 
-use crate::file_filter::FileFilter;
+use crate::ctags::ctags_cache::{self, CtagsCache};
+use crate::ctags::ctags_coverage::Coverage;
+use crate::ctags::ctags_lang;
+use crate::ctags::rust_module::CrateRoots;
+use crate::workspace::Module;
 
+pub mod class_filter;
+pub mod cli;
 pub mod coco_struct;
+pub mod config;
 pub mod ctags;
+pub mod document;
 pub mod file_filter;
 pub mod parse_option;
 pub mod render;
 pub mod segment;
+pub mod source;
+pub mod workspace;
+
+#[cfg(test)]
+mod testing;
+
+/// batches of files for each ctags thread, see `batches_by_size`
+const BATCHES_PER_THREAD: usize = 4;
 
 /// Returns Vec<ClassInfo> with the given path.
 ///
@@ -36,11 +60,17 @@ pub mod segment;
 /// use modeling::render::PlantUmlRender;
 ///
 /// use modeling::file_filter::FileFilter;
-/// let classes = by_dir("src/",FileFilter::default(), &ParseOption::default());
+/// let classes = by_dir("src/",FileFilter::default(), &ParseOption::default()).unwrap();
 /// let puml = PlantUmlRender::render(&classes, &ParseOption::default());
 /// ```
-pub fn by_dir<P: AsRef<Path>>(path: P, filter: FileFilter, option: &ParseOption) -> Vec<ClassInfo> {
-    by_files(files_from_path(path, filter), option)
+pub fn by_dir<P: AsRef<Path>>(
+    path: P,
+    filter: FileFilter,
+    option: &ParseOption,
+) -> Result<Vec<ClassInfo>, Error> {
+    let root = path.as_ref();
+    let files = files_from_path(root, root, filter);
+    run_ctags(&ctags_opt(&files, option), root, files, option)
 }
 
 /// Returns Vec<ClassInfo> with the given files.
@@ -57,64 +87,347 @@ pub fn by_dir<P: AsRef<Path>>(path: P, filter: FileFilter, option: &ParseOption)
 ///
 /// let mut files = vec![];
 /// files.push("src/lib.rs".to_string());
-/// let classes = by_files(files, &ParseOption::default());
+/// let classes = by_files(files, &ParseOption::default()).unwrap();
 /// let puml = PlantUmlRender::render(&classes, &ParseOption::default());
 /// ```
-pub fn by_files(files: Vec<String>, option: &ParseOption) -> Vec<ClassInfo> {
-    let thread = count_thread(&files);
-    let opt = build_opt(thread);
+pub fn by_files(files: Vec<String>, option: &ParseOption) -> Result<Vec<ClassInfo>, Error> {
+    let opt = ctags_opt(&files, option);
+
+    run_ctags(&opt, Path::new(""), files, option)
+}
 
-    run_ctags(&opt, &files_by_thread(files, &opt), option)
+/// Returns the version of ctags in `PATH`, fails with the reason if it's missing or unsupported.
+pub fn check_ctags() -> Result<CtagsVersion, Error> {
+    CmdCtags::version(&build_opt(1))
 }
 
-fn count_thread(origin_files: &[String]) -> usize {
-    let mut thread = origin_files.len();
-    let default_ptags_thread = 8;
-    if thread >= default_ptags_thread {
-        thread = default_ptags_thread;
+/// Returns Vec<ClassInfo> of all modules in `root` in one model, so types are resolved across
+/// modules. Files of each module are walked with the filter at the same index of `filters`, globs
+/// are relative to `root`, a file of nested modules belongs to the innermost one. Each class is
+/// tagged with the name of its module, see `workspace::tag_modules`.
+pub fn by_modules<P: AsRef<Path>>(
+    root: P,
+    modules: &[Module],
+    filters: &[FileFilter],
+    option: &ParseOption,
+) -> Result<Vec<ClassInfo>, Error> {
+    let mut files = vec![];
+    for (module, filter) in modules.iter().zip(filters) {
+        files.extend(
+            files_from_path(&module.path, root.as_ref(), filter.clone())
+                .into_iter()
+                .filter(|file| workspace::module_of(Path::new(file), modules) == Some(module)),
+        );
     }
-    thread
+    files.sort();
+
+    let mut classes = run_ctags(&ctags_opt(&files, option), root.as_ref(), files, option)?;
+    workspace::tag_modules(&mut classes, modules);
+    Ok(classes)
 }
 
-fn run_ctags(opt: &Opt, files: &[String], option: &ParseOption) -> Vec<ClassInfo> {
-    let outputs = CmdCtags::call(opt, files).unwrap();
-    let mut iters = Vec::new();
-    for o in &outputs {
-        let iter = if opt.validate_utf8 {
-            std::str::from_utf8(&o.stdout).unwrap().lines()
+/// Returns Vec<ClassInfo> of files listed by `git ls-files` in `opt.dir`, so only committed files
+/// are analysed. `opt` decides untracked, ignored, submodule and git-lfs files.
+///
+/// # Examples
+///
+/// ```no_run
+/// use modeling::{by_git, Opt, ParseOption};
+/// use modeling::file_filter::FileFilter;
+/// use structopt::StructOpt;
+///
+/// let mut opt = Opt::from_iter(vec!["ptags", "--exclude-lfs"].iter());
+/// opt.dir = "src/".into();
+/// let classes = by_git(&opt, FileFilter::default(), &ParseOption::default()).unwrap();
+/// ```
+pub fn by_git(opt: &Opt, filter: FileFilter, option: &ParseOption) -> Result<Vec<ClassInfo>, Error> {
+    let files = files_from_git(opt, filter)?;
+
+    let mut ctags_opt = ctags_opt(&files, option);
+    ctags_opt.dir = opt.dir.clone();
+    ctags_opt.bin_ctags = opt.bin_ctags.clone();
+
+    run_ctags(&ctags_opt, Path::new(""), files, option)
+}
+
+/// Returns Vec<ClassInfo> of `rev` in the git repository of `opt.dir`, like `v0.6.2` or `HEAD~1`.
+///
+/// Files of the revision are written into a temp dir without checking out, file names of classes
+/// are relative to `opt.dir` like the output of `by_git`.
+pub fn by_rev(
+    opt: &Opt,
+    rev: &str,
+    filter: FileFilter,
+    option: &ParseOption,
+) -> Result<Vec<ClassInfo>, Error> {
+    let snapshot = TempDir::new()?;
+    let files = files_from_rev(opt, rev, filter, snapshot.path())?;
+
+    let mut ctags_opt = ctags_opt(&files, option);
+    ctags_opt.dir = snapshot.path().to_path_buf();
+    ctags_opt.bin_ctags = opt.bin_ctags.clone();
+
+    run_ctags(&ctags_opt, Path::new(""), files, option)
+}
+
+/// Returns file and package dependencies of `rev` in the git repository of `opt.dir`.
+pub fn deps_by_rev(opt: &Opt, rev: &str, filter: FileFilter) -> Result<DependencyGraph, Error> {
+    let snapshot = TempDir::new()?;
+    let files = files_from_rev(opt, rev, filter, snapshot.path())?;
+
+    Ok(DependencyGraph::build(snapshot.path(), &files))
+}
+
+/// Returns file and package dependencies, parsed from import statements of files in the path.
+///
+/// # Examples
+///
+/// ```
+/// use modeling::deps_by_dir;
+/// use modeling::file_filter::FileFilter;
+///
+/// let graph = deps_by_dir("src/", FileFilter::default());
+/// ```
+pub fn deps_by_dir<P: AsRef<Path>>(path: P, filter: FileFilter) -> DependencyGraph {
+    let root = path.as_ref();
+    let files: Vec<String> = files_from_path(root, root, filter)
+        .into_iter()
+        .map(|file| match Path::new(&file).strip_prefix(root) {
+            Ok(relative) => format!("{}", relative.display()),
+            Err(_) => file,
+        })
+        .collect();
+    deps_by_files(root, &files)
+}
+
+/// Returns file and package dependencies, parsed from import statements of the given files,
+/// which are relative to `root`.
+pub fn deps_by_files<P: AsRef<Path>>(root: P, files: &[String]) -> DependencyGraph {
+    DependencyGraph::build(root, files)
+}
+
+/// Returns `option.threads`, or the number of CPUs by default, but not more than files.
+fn count_thread(origin_files: &[String], option: &ParseOption) -> usize {
+    let thread = match option.threads {
+        0 => std::thread::available_parallelism().map_or(1, |count| count.get()),
+        threads => threads,
+    };
+    thread.min(origin_files.len())
+}
+
+/// Returns options of ctags processes for `files`.
+fn ctags_opt(files: &[String], option: &ParseOption) -> Opt {
+    let mut opt = build_opt(count_thread(files, option));
+    opt.timeout = option.timeout;
+    opt.opt_ctags
+        .extend(ctags_lang::ctags_options(&option.languages));
+    opt
+}
+
+/// Parses `files` by ctags, ids of classes are built from their files relative to `root`.
+fn run_ctags(
+    opt: &Opt,
+    root: &Path,
+    files: Vec<String>,
+    option: &ParseOption,
+) -> Result<Vec<ClassInfo>, Error> {
+    let mut parser = CtagsParser::default();
+    parser.option = option.clone();
+    parser.crate_roots = CrateRoots::find(&opt.dir, &files);
+    let mut on_line = |line: &str| parser.parse_line(line);
+    let version = CmdCtags::version(opt)?;
+    if option.cache_dir.is_empty() {
+        stream_ctags(opt, &version, files, &mut on_line)?;
+    } else {
+        cached_ctags(opt, &version, files, option, &mut on_line)?;
+    }
+
+    if !option.coverage_file.is_empty() {
+        report_coverage(&parser.coverage(), &option.coverage_file)?;
+    }
+    let crate_roots = std::mem::take(&mut parser.crate_roots);
+    let mut classes = parser.classes();
+    let files = source::read_sources(&mut classes, &opt.dir, &crate_roots);
+    // ids need the packages of Java and Kotlin files, which are read by `read_sources`
+    for clazz in classes.iter_mut() {
+        clazz.id = clazz.stable_id(root);
+    }
+    let mut classes = option.class_filter.filter(classes);
+    source::resolve::resolve_types(&mut classes, &files);
+    Ok(classes)
+}
+
+/// Writes the coverage report of parsing to `file` as JSON, its summary is printed.
+fn report_coverage(coverage: &Coverage, file: &str) -> Result<(), Error> {
+    fs::write(file, serde_json::to_string_pretty(coverage)?)?;
+    eprintln!("{}", coverage.summary());
+    Ok(())
+}
+
+/// Passes tag lines of files to `on_line` while ctags is running, warnings of ctags are printed
+/// with their files. Returns files of batches which timed out, their tags may be incomplete.
+fn stream_ctags(
+    opt: &Opt,
+    version: &CtagsVersion,
+    files: Vec<String>,
+    on_line: &mut dyn FnMut(&str),
+) -> Result<Vec<String>, Error> {
+    let files = batches_by_size(files, opt);
+    let streamed = CmdCtags::stream(opt, version, &files, on_line)?;
+    for warning in CmdCtags::warnings(&streamed.outputs, &files) {
+        if warning.file.is_empty() {
+            eprintln!("warning: {}", warning.message);
         } else {
-            unsafe { std::str::from_utf8_unchecked(&o.stdout).lines() }
-        };
-        iters.push(iter);
+            eprintln!("warning: {}: {}", warning.file, warning.message);
+        }
     }
 
-    let mut parser = CtagsParser::parse_str(iters);
+    Ok(streamed.timed_out)
+}
 
-    parser.option = option.clone();
-    parser.classes()
+/// Passes tag lines like `stream_ctags`, but only files changed since the last run are parsed by
+/// ctags, tags of others are read from the cache in `option.cache_dir`.
+fn cached_ctags(
+    opt: &Opt,
+    version: &CtagsVersion,
+    files: Vec<String>,
+    option: &ParseOption,
+    on_line: &mut dyn FnMut(&str),
+) -> Result<(), Error> {
+    let cache = CtagsCache::open(&option.cache_dir, &CtagsCache::key(opt, version))?;
+
+    let hashes: Vec<Option<String>> = files
+        .iter()
+        .map(|file| CtagsCache::hash(&opt.dir, file))
+        .collect();
+    let changed: Vec<String> = files
+        .iter()
+        .zip(&hashes)
+        .filter(|(_, hash)| !hash.as_ref().is_some_and(|hash| cache.contains(hash)))
+        .map(|(file, _)| file.clone())
+        .collect();
+
+    // tags of changed files are parsed as they stream, their lines are only kept for the cache
+    let mut by_file: HashMap<String, Vec<String>> = HashMap::new();
+    let mut timed_out = HashSet::new();
+    if !changed.is_empty() {
+        let mut ctags_opt = ctags_opt(&changed, option);
+        ctags_opt.dir = opt.dir.clone();
+        ctags_opt.bin_ctags = opt.bin_ctags.clone();
+        timed_out = stream_ctags(&ctags_opt, version, changed, &mut |line| {
+            on_line(line);
+            if let Some(file) = ctags_cache::file_of(line) {
+                by_file
+                    .entry(file.to_string())
+                    .or_default()
+                    .push(line.to_string());
+            }
+        })?
+        .into_iter()
+        .collect();
+    }
+
+    // cached tags are read and parsed file by file
+    for (file, hash) in files.iter().zip(&hashes) {
+        let hash = match hash {
+            Some(hash) => hash,
+            None => continue,
+        };
+        match cache.get(hash) {
+            Some(cached) => {
+                for line in &cached {
+                    on_line(line);
+                }
+            }
+            // tags of a killed ctags may be empty or truncated, they're parsed again next time
+            None if !timed_out.contains(file) => {
+                let lines = by_file.remove(file).unwrap_or_default();
+                cache.put(hash, &lines)?;
+            }
+            None => {}
+        }
+    }
+
+    let used: HashSet<&str> = hashes.iter().flatten().map(String::as_str).collect();
+    cache.retain(&used)?;
+    Ok(())
 }
 
-fn files_from_path<P: AsRef<Path>>(path: P, filter: FileFilter) -> Vec<String> {
+/// Returns files walked from `path`, globs of `filter` are relative to `root`.
+fn files_from_path<P: AsRef<Path>>(path: P, root: &Path, filter: FileFilter) -> Vec<String> {
     let mut origin_files = vec![];
     for entry in Walk::new(path).flatten() {
-        if entry.file_type().unwrap().is_file() {
-            let buf = entry.path().to_path_buf();
-            if filter.allow(buf) {
-                origin_files.push(format!("{}", entry.path().display()))
-            }
+        let path = entry.path();
+        if entry.file_type().unwrap().is_file()
+            && filter.allow_path_in(root, path)
+            && filter.allow_content(path)
+        {
+            origin_files.push(format!("{}", path.display()))
         }
     }
 
     origin_files
 }
 
-fn files_by_thread(origin_files: Vec<String>, opt: &Opt) -> Vec<String> {
-    let mut files = vec![String::from(""); opt.thread];
-    for (i, f) in origin_files.iter().enumerate() {
-        files[i % opt.thread].push_str(f);
-        files[i % opt.thread].push('\n');
+fn files_from_git(opt: &Opt, filter: FileFilter) -> Result<Vec<String>, Error> {
+    let mut files = vec![];
+    for file in CmdGit::get_files(opt)? {
+        let path = Path::new(&file);
+        if filter.allow_path(path) && filter.allow_content(&opt.dir.join(path)) {
+            files.push(file);
+        }
+    }
+
+    Ok(files)
+}
+
+fn files_from_rev(
+    opt: &Opt,
+    rev: &str,
+    filter: FileFilter,
+    snapshot: &Path,
+) -> Result<Vec<String>, Error> {
+    let entries: Vec<TreeEntry> = CmdGit::ls_tree(opt, rev)?
+        .into_iter()
+        .filter(|entry| filter.allow_path(Path::new(&entry.path)))
+        .collect();
+    CmdGit::write_blobs(opt, &entries, snapshot)?;
+
+    Ok(entries
+        .into_iter()
+        .map(|entry| entry.path)
+        .filter(|file| filter.allow_content(&snapshot.join(file)))
+        .collect())
+}
+
+/// Splits files into lists of similar byte sizes, a few lists for each thread, so large files
+/// are spread over ctags processes and a thread with small lists can take the next one.
+fn batches_by_size(origin_files: Vec<String>, opt: &Opt) -> Vec<String> {
+    let count = (opt.thread * BATCHES_PER_THREAD).min(origin_files.len());
+    if count == 0 {
+        return vec![];
+    }
+
+    let mut sized: Vec<(u64, String)> = origin_files
+        .into_iter()
+        .map(|file| {
+            let size = fs::metadata(opt.dir.join(&file)).map_or(0, |meta| meta.len());
+            (size, file)
+        })
+        .collect();
+    sized.sort_by_key(|(size, _)| Reverse(*size));
+
+    // the largest file goes to the smallest batch first
+    let mut sizes: BinaryHeap<Reverse<(u64, usize)>> =
+        (0..count).map(|index| Reverse((0, index))).collect();
+    let mut batches = vec![String::new(); count];
+    for (size, file) in sized {
+        let Reverse((total, index)) = sizes.pop().unwrap();
+        batches[index].push_str(&file);
+        batches[index].push('\n');
+        sizes.push(Reverse((total + size, index)));
     }
-    files
+    batches
 }
 
 fn build_opt(thread: usize) -> Opt {
@@ -130,9 +443,17 @@ mod tests {
     use std::fs;
     use std::path::PathBuf;
 
+    use crate::ctags::ctags_cache::CtagsCache;
     use crate::file_filter::FileFilter;
     use crate::render::{MermaidRender, PlantUmlRender};
-    use crate::{by_dir, ParseOption};
+    use crate::{
+        batches_by_size, by_dir, cached_ctags, count_thread, deps_by_dir, deps_by_rev,
+        files_from_git, files_from_path, files_from_rev, CmdCtags, Opt, ParseOption,
+    };
+    use std::path::Path;
+    use std::process::Command;
+    use structopt::StructOpt;
+    use tempfile::TempDir;
 
     pub fn ctags_fixtures_dir() -> PathBuf {
         PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -146,7 +467,7 @@ mod tests {
     fn should_run_struct_analysis() {
         let path = format!("{}", ctags_fixtures_dir().display());
         let option = ParseOption::default();
-        let vec = by_dir(path, FileFilter::default(), &option);
+        let vec = by_dir(path, FileFilter::default(), &option).unwrap();
 
         assert_eq!(3, vec.len());
         let result = PlantUmlRender::render(&vec, &option);
@@ -171,7 +492,8 @@ mod tests {
             path,
             FileFilter::new(vec![], suffixes, "".to_string()),
             &option,
-        );
+        )
+        .unwrap();
 
         assert_eq!(3, vec.len());
         let result = PlantUmlRender::render(&vec, &option);
@@ -193,7 +515,8 @@ mod tests {
             path,
             FileFilter::new(vec![], vec![], "".to_string()),
             &option,
-        );
+        )
+        .unwrap();
 
         assert_eq!(3, vec.len());
     }
@@ -207,7 +530,7 @@ mod tests {
 
         let option = ParseOption::default();
         let filter = FileFilter::new(vec![], vec![], "store.go".to_string());
-        let vec = by_dir(path, filter, &option);
+        let vec = by_dir(path, filter, &option).unwrap();
 
         assert_eq!(3, vec.len());
     }
@@ -216,7 +539,7 @@ mod tests {
     fn should_render_mermaid() {
         let path = format!("{}", ctags_fixtures_dir().display());
         let option = ParseOption::default();
-        let vec = by_dir(path, FileFilter::default(), &option);
+        let vec = by_dir(path, FileFilter::default(), &option).unwrap();
 
         assert_eq!(3, vec.len());
         let result = MermaidRender::render(&vec, &option);
@@ -226,4 +549,255 @@ mod tests {
         assert!(result.contains("Animal <|-- Horse"));
         assert!(result.contains("Animal <|-- Snake"));
     }
+
+    #[test]
+    fn should_filter_files_from_git() {
+        let mut opt = Opt::from_iter(["ptags"].iter());
+        opt.dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+        let filter = FileFilter::default().with_includes(vec!["src/ctags/*_cmd.rs".to_string()]);
+        let files = files_from_git(&opt, filter).unwrap();
+
+        assert!(files.contains(&"src/ctags/ctags_cmd.rs".to_string()));
+        assert!(files.iter().all(|file| file.ends_with("_cmd.rs")));
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    fn commit_all(dir: &Path, message: &str) {
+        git(dir, &["add", "-A"]);
+        git(
+            dir,
+            &[
+                "-c",
+                "user.name=modeling",
+                "-c",
+                "user.email=modeling@inherd.org",
+                "commit",
+                "-q",
+                "-m",
+                message,
+            ],
+        );
+    }
+
+    #[test]
+    fn should_analyse_revision_without_checkout() {
+        let repo = TempDir::new().unwrap();
+        let dir = repo.path();
+        git(dir, &["init", "-q"]);
+        fs::create_dir_all(dir.join("order")).unwrap();
+        fs::create_dir_all(dir.join("user")).unwrap();
+        let order = dir.join("order").join("Order.java");
+        fs::write(&order, "package shop.order;\nimport shop.user.User;\nclass Order {}").unwrap();
+        fs::write(dir.join("user").join("User.java"), "package shop.user;\nclass User {}").unwrap();
+        commit_all(dir, "first");
+        git(dir, &["tag", "v1"]);
+
+        fs::write(&order, "package shop.order;\nclass Order {}").unwrap();
+        commit_all(dir, "second");
+
+        let mut opt = Opt::from_iter(["ptags"].iter());
+        opt.dir = dir.to_path_buf();
+
+        let graph = deps_by_rev(&opt, "v1", FileFilter::default()).unwrap();
+        assert_eq!(1, graph.package_edges.len());
+        assert_eq!("shop.order", graph.package_edges[0].source);
+        assert_eq!("order/Order.java", graph.file_edges[0].source);
+
+        let graph = deps_by_rev(&opt, "HEAD", FileFilter::default()).unwrap();
+        assert!(graph.package_edges.is_empty());
+
+        let filter = FileFilter::default().with_content("import".to_string());
+        let snapshot = TempDir::new().unwrap();
+        let files = files_from_rev(&opt, "v1", filter, snapshot.path()).unwrap();
+        assert_eq!(vec!["order/Order.java"], files);
+    }
+
+    #[test]
+    fn should_build_dependencies_of_source() {
+        let root_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let path = format!("{}", root_dir.join("src").display());
+
+        let graph = deps_by_dir(path, FileFilter::default());
+
+        assert!(graph
+            .file_edges
+            .iter()
+            .any(|edge| edge.source == "lib.rs" && edge.target == "file_filter.rs"));
+        assert!(graph
+            .package_edges
+            .iter()
+            .any(|edge| edge.source == "crate::render" && edge.target == "crate"));
+    }
+
+    #[test]
+    fn should_match_globs_relative_to_input() {
+        let temp = TempDir::new().unwrap();
+        let input = temp.path().join("shop");
+        fs::create_dir_all(input.join("main")).unwrap();
+        fs::create_dir_all(input.join("test")).unwrap();
+        let order = input.join("main").join("Order.java");
+        fs::write(&order, "class Order {}").unwrap();
+        fs::write(input.join("test").join("Test.java"), "class Test {}").unwrap();
+
+        let filter = FileFilter::default().with_includes(vec!["main/**".to_string()]);
+        let files = files_from_path(&input, &input, filter);
+
+        assert_eq!(vec![format!("{}", order.display())], files);
+    }
+
+    #[test]
+    fn should_balance_batches_by_size() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        fs::write(dir.join("Generated.java"), "x".repeat(1000)).unwrap();
+        let mut files = vec!["Generated.java".to_string()];
+        for name in ["A", "B", "C", "D", "E", "F"].iter() {
+            fs::write(dir.join(format!("{}.java", name)), "x".repeat(100)).unwrap();
+            files.push(format!("{}.java", name));
+        }
+
+        let mut opt = Opt::from_iter(["ptags", "-t", "1"].iter());
+        opt.dir = dir.to_path_buf();
+        let batches = batches_by_size(files, &opt);
+
+        assert_eq!(4, batches.len());
+        assert_eq!("Generated.java\n", batches[0]);
+        for batch in &batches[1..] {
+            assert_eq!(2, batch.lines().count());
+        }
+    }
+
+    #[test]
+    fn should_count_thread_by_option() {
+        let files = vec!["a.rs".to_string(), "b.rs".to_string()];
+        let mut option = ParseOption::default();
+
+        assert!(count_thread(&files, &option) >= 1);
+        assert!(count_thread(&files, &option) <= 2);
+        option.threads = 1;
+        assert_eq!(1, count_thread(&files, &option));
+        option.threads = 16;
+        assert_eq!(2, count_thread(&files, &option));
+        assert_eq!(0, count_thread(&[], &option));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_only_parse_changed_files_with_cache() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        // prints a struct for each file and logs the files it parsed
+        let ctags = dir.join("ctags");
+        fs::write(
+            &ctags,
+            "#!/bin/sh\n\
+             if [ \"$1\" = \"--version\" ]; then echo 'Universal Ctags 5.9.0'; exit 0; fi\n\
+             while read f; do\n\
+               printf '%s\\t%s\\t/^struct %s;$/;\"\\tkind:struct\\tline:1\\tlanguage:Rust\\n' \"${f%.rs}\" \"$f\" \"${f%.rs}\"\n\
+               echo \"$f\" >> parsed.log\n\
+             done\n",
+        )
+        .unwrap();
+        fs::set_permissions(&ctags, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(dir.join("A.rs"), "struct A;").unwrap();
+        fs::write(dir.join("B.rs"), "struct B;").unwrap();
+
+        let mut opt = Opt::from_iter(["ptags"].iter());
+        opt.dir = dir.to_path_buf();
+        opt.bin_ctags = ctags;
+        let option = ParseOption {
+            cache_dir: dir.join("cache").to_str().unwrap().to_string(),
+            ..ParseOption::default()
+        };
+        let files = vec!["A.rs".to_string(), "B.rs".to_string()];
+        let parsed = || {
+            let log = fs::read_to_string(dir.join("parsed.log")).unwrap_or_default();
+            let mut files: Vec<String> = log.lines().map(|line| line.to_string()).collect();
+            files.sort();
+            files
+        };
+
+        let version = CmdCtags::version(&opt).unwrap();
+        let mut lines = vec![];
+        cached_ctags(&opt, &version, files.clone(), &option, &mut |line| {
+            lines.push(line.to_string())
+        })
+        .unwrap();
+        assert_eq!(2, lines.len());
+        assert_eq!(vec!["A.rs", "B.rs"], parsed());
+
+        let old_hash = CtagsCache::hash(dir, "B.rs").unwrap();
+        fs::write(dir.join("B.rs"), "struct B {}").unwrap();
+        fs::remove_file(dir.join("parsed.log")).unwrap();
+        let mut cached = vec![];
+        cached_ctags(&opt, &version, files, &option, &mut |line| {
+            cached.push(line.to_string())
+        })
+        .unwrap();
+        // tags of changed files are parsed before the cached ones
+        lines.sort();
+        cached.sort();
+        assert_eq!(lines, cached);
+        assert_eq!(vec!["B.rs"], parsed());
+        let cache = CtagsCache::open(&option.cache_dir, &CtagsCache::key(&opt, &version)).unwrap();
+        assert!(!cache.contains(&old_hash));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_not_cache_files_of_timed_out_batch() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        // hangs on the file `Slow.rs`
+        let ctags = dir.join("ctags");
+        fs::write(
+            &ctags,
+            "#!/bin/sh\n\
+             if [ \"$1\" = \"--version\" ]; then echo 'Universal Ctags 5.9.0'; exit 0; fi\n\
+             while read f; do\n\
+               if [ \"$f\" = Slow.rs ]; then exec sleep 30; fi\n\
+               printf '%s\\t%s\\t/^struct %s;$/;\"\\tkind:struct\\tline:1\\tlanguage:Rust\\n' \"${f%.rs}\" \"$f\" \"${f%.rs}\"\n\
+             done\n",
+        )
+        .unwrap();
+        fs::set_permissions(&ctags, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(dir.join("A.rs"), "struct A;").unwrap();
+        fs::write(dir.join("Slow.rs"), "struct Slow;").unwrap();
+
+        let mut opt = Opt::from_iter(["ptags"].iter());
+        opt.dir = dir.to_path_buf();
+        opt.bin_ctags = ctags;
+        let option = ParseOption {
+            cache_dir: dir.join("cache").to_str().unwrap().to_string(),
+            threads: 2,
+            timeout: 1,
+            ..ParseOption::default()
+        };
+        let files = vec!["A.rs".to_string(), "Slow.rs".to_string()];
+
+        let version = CmdCtags::version(&opt).unwrap();
+        let mut lines = vec![];
+        cached_ctags(&opt, &version, files, &option, &mut |line| {
+            lines.push(line.to_string())
+        })
+        .unwrap();
+
+        assert_eq!(1, lines.len());
+        let cache = CtagsCache::open(&option.cache_dir, &CtagsCache::key(&opt, &version)).unwrap();
+        assert!(cache.contains(&CtagsCache::hash(dir, "A.rs").unwrap()));
+        assert!(!cache.contains(&CtagsCache::hash(dir, "Slow.rs").unwrap()));
+    }
 }
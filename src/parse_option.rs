@@ -1,11 +1,36 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
+use crate::class_filter::ClassFilter;
+use crate::ctags::ctags_lang::CtagsLanguage;
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ParseOption {
     pub merge_method_name: bool,
+    pub merge_overloads: bool,
     pub field_only: bool,
     pub inline_id_suffix: bool,
     pub without_parent: bool,
     pub without_impl_suffix: bool,
     pub without_suffix: String,
+    /// classes to keep after parsing
+    #[serde(default)]
+    pub class_filter: ClassFilter,
+    /// dir of the cache of ctags output, like `.modeling/cache` in the input dir, empty to parse all files by ctags
+    #[serde(default)]
+    pub cache_dir: String,
+    /// number of ctags processes run at once, 0 for the number of CPUs
+    #[serde(default)]
+    pub threads: usize,
+    /// seconds before a ctags process is killed, 0 to wait until it exits
+    #[serde(default)]
+    pub timeout: u64,
+    /// extra options of ctags and regex languages by language name, see `CtagsLanguage`
+    #[serde(default)]
+    pub languages: BTreeMap<String, CtagsLanguage>,
+    /// file of the coverage report of tag lines dropped by the parser, like
+    /// `modeling_coverage.json`, empty for no report
+    #[serde(default)]
+    pub coverage_file: String,
 }
@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+
+use crate::source::dependency::DependencyEdge;
+
+/// Render file or package dependencies to string, edges are labeled by their weight
+pub struct DependencyRender;
+
+impl DependencyRender {
+    pub fn plantuml(edges: &[DependencyEdge]) -> String {
+        let ids = Self::node_ids(edges);
+        let mut rendered = vec![];
+        for (name, id) in &ids {
+            rendered.push(format!("component \"{}\" as {}", name, id));
+        }
+
+        let mut deps = vec![];
+        for edge in edges {
+            deps.push(format!(
+                "{} --> {} : {}\n",
+                ids[&edge.source], ids[&edge.target], edge.weight
+            ));
+        }
+
+        format!(
+            "@startuml\n\n{}\n\n{}\n@enduml",
+            rendered.join("\n"),
+            deps.join("")
+        )
+    }
+
+    pub fn mermaid(edges: &[DependencyEdge]) -> String {
+        let space = "    ";
+        let ids = Self::node_ids(edges);
+        let mut rendered = vec![];
+        for (name, id) in &ids {
+            rendered.push(format!("{}{}[\"{}\"]\n", space, id, name));
+        }
+
+        let mut deps = vec![];
+        for edge in edges {
+            deps.push(format!(
+                "{}{} -->|{}| {}\n",
+                space, ids[&edge.source], edge.weight, ids[&edge.target]
+            ));
+        }
+
+        format!("graph LR\n{}{}", rendered.join(""), deps.join(""))
+    }
+
+    pub fn graphviz(edges: &[DependencyEdge]) -> String {
+        let mut deps = vec![];
+        for edge in edges {
+            deps.push(format!(
+                "  \"{}\" -> \"{}\" [label=\"{}\", weight={}]\n",
+                edge.source, edge.target, edge.weight, edge.weight
+            ));
+        }
+
+        format!(
+            "digraph G {{\n  rankdir=LR\n  node[shape=box]\n{}}}",
+            deps.join("")
+        )
+    }
+
+    /// PlantUML and Mermaid can't use paths as node id, so nodes are numbered by name
+    fn node_ids(edges: &[DependencyEdge]) -> BTreeMap<String, String> {
+        let mut ids: BTreeMap<String, String> = BTreeMap::new();
+        for edge in edges {
+            ids.insert(edge.source.clone(), "".to_string());
+            ids.insert(edge.target.clone(), "".to_string());
+        }
+
+        for (index, id) in ids.values_mut().enumerate() {
+            *id = format!("n{}", index);
+        }
+        ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::render::dependency_render::DependencyRender;
+    use crate::source::dependency::DependencyEdge;
+
+    fn edges() -> Vec<DependencyEdge> {
+        vec![DependencyEdge {
+            source: "crate::render".to_string(),
+            target: "crate".to_string(),
+            weight: 3,
+        }]
+    }
+
+    #[test]
+    fn should_render_plantuml_components() {
+        assert_eq!(
+            "@startuml\n\ncomponent \"crate\" as n0\ncomponent \"crate::render\" as n1\n\nn1 --> n0 : 3\n\n@enduml",
+            DependencyRender::plantuml(&edges())
+        );
+    }
+
+    #[test]
+    fn should_render_mermaid_graph() {
+        assert_eq!(
+            "graph LR\n    n0[\"crate\"]\n    n1[\"crate::render\"]\n    n1 -->|3| n0\n",
+            DependencyRender::mermaid(&edges())
+        );
+    }
+
+    #[test]
+    fn should_render_graphviz_with_weight() {
+        assert_eq!(
+            "digraph G {\n  rankdir=LR\n  node[shape=box]\n  \"crate::render\" -> \"crate\" [label=\"3\", weight=3]\n}",
+            DependencyRender::graphviz(&edges())
+        );
+    }
+}
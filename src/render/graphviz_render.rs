@@ -1,10 +1,9 @@
 use std::collections::HashMap;
-use std::fs;
 
 use serde::{Deserialize, Serialize};
 
 use crate::coco_struct::ClassInfo;
-use crate::render::{process_name, render_member, render_method};
+use crate::render::{process_name, render_member, render_method, ClassIndex};
 use crate::ParseOption;
 
 /// Render classes info to string
@@ -18,13 +17,17 @@ pub struct DData {
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct DNode {
+    /// id of the class, or its name if the class has no id
     id: String,
+    name: String,
     package: String,
     group: usize,
+    annotations: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct DLink {
+    /// ids of nodes
     source: String,
     target: String,
     value: usize,
@@ -33,10 +36,19 @@ pub struct DLink {
 
 impl GraphvizRender {
     pub fn render(classes: &Vec<ClassInfo>, parse_option: &ParseOption) -> String {
+        Self::render_with_data(classes, parse_option).0
+    }
+
+    /// Returns the graph with the data of the D3 visualization, which is served from `output.json`.
+    pub fn render_with_data(
+        classes: &Vec<ClassInfo>,
+        parse_option: &ParseOption,
+    ) -> (String, DData) {
         let mut sub_graphs_map: HashMap<String, Vec<String>> = HashMap::default();
         let mut deps: Vec<String> = vec![];
         let mut data = DData::default();
 
+        let index = ClassIndex::new(classes, parse_option);
         let mut class_map: HashMap<String, bool> = HashMap::default();
         for clazz in classes {
             class_map.insert(process_name(&parse_option, &clazz.name), true);
@@ -45,8 +57,9 @@ impl GraphvizRender {
         let class_catalog = Self::catalog_mvc_to_index();
         let layer_cluster = Self::index_to_mvc_cluster();
 
-        for clazz in classes {
+        for (clazz_index, clazz) in classes.iter().enumerate() {
             let mut dep_map: HashMap<String, String> = HashMap::default();
+            let class_name = index.label(clazz_index);
 
             Self::create_data_nodes(
                 &mut sub_graphs_map,
@@ -54,7 +67,7 @@ impl GraphvizRender {
                 &class_catalog,
                 &layer_cluster,
                 &clazz,
-                &parse_option
+                class_name,
             );
 
             let _ = render_member(&clazz, &mut dep_map, "", parse_option, &mut class_map);
@@ -62,25 +75,21 @@ impl GraphvizRender {
                 let _ = render_method(&clazz, &mut dep_map, "", parse_option);
             }
 
-            for (callee, current_clz) in dep_map {
-                if callee == current_clz {
-                    continue;
-                }
-
-                if class_map.get(&callee).is_none() {
-                    continue;
-                }
-
+            for callee in index.deps(clazz_index, &dep_map) {
+                let callee_name = index.label(callee);
                 // for service -> repository
-                if current_clz.ends_with("Service") && callee.ends_with("Repository") {
-                    deps.push(format!("{} -> {} [color=\"red\"] \n", current_clz, callee));
+                if class_name.ends_with("Service") && callee_name.ends_with("Repository") {
+                    deps.push(format!(
+                        "{} -> {} [color=\"red\"] \n",
+                        class_name, callee_name
+                    ));
                 } else {
-                    deps.push(format!("{} -> {}\n", current_clz, callee));
+                    deps.push(format!("{} -> {}\n", class_name, callee_name));
                 }
 
                 data.links.push(DLink {
-                    source: current_clz,
-                    target: callee,
+                    source: Self::node_id(clazz, class_name),
+                    target: Self::node_id(&classes[callee], callee_name),
                     package: clazz.package.clone(),
                     value: 1,
                 })
@@ -96,9 +105,7 @@ impl GraphvizRender {
             ));
         }
 
-        let _ = fs::write("output.json", serde_json::to_string(&data).unwrap());
-
-        format!(
+        let graph = format!(
             "digraph G {{
   compound=true;
   ranksep=1
@@ -106,7 +113,8 @@ impl GraphvizRender {
 {}\n{}\n}}",
             sub_graphs.join("\n"),
             deps.join("")
-        )
+        );
+        (graph, data)
     }
 
     fn create_data_nodes(
@@ -115,10 +123,9 @@ impl GraphvizRender {
         class_catalog: &HashMap<&str, usize>,
         layer_cluster: &HashMap<usize, &str>,
         clazz: &ClassInfo,
-        parse_option: &ParseOption,
+        class_name: &str,
     ) {
         let mut has_catalog = false;
-        let class_name = process_name(&parse_option, &clazz.name);
         for (key, value) in class_catalog {
             if class_name.ends_with(key) {
                 has_catalog = true;
@@ -127,22 +134,34 @@ impl GraphvizRender {
                 graph.push(class_name.to_string());
 
                 data.nodes.push(DNode {
-                    id: class_name.to_string(),
+                    id: Self::node_id(clazz, class_name),
+                    name: class_name.to_string(),
                     package: clazz.package.to_string(),
                     group: *value,
+                    annotations: clazz.annotations.clone(),
                 })
             }
         }
 
         if !has_catalog {
             data.nodes.push(DNode {
-                id: class_name.to_string(),
+                id: Self::node_id(clazz, class_name),
+                name: class_name.to_string(),
                 package: clazz.package.to_string(),
                 group: 4,
+                annotations: clazz.annotations.clone(),
             })
         }
     }
 
+    fn node_id(clazz: &ClassInfo, class_name: &str) -> String {
+        if clazz.id.is_empty() {
+            class_name.to_string()
+        } else {
+            clazz.id.clone()
+        }
+    }
+
     fn catalog_mvc_to_index() -> HashMap<&'static str, usize> {
         let mut class_catalog: HashMap<&str, usize> = HashMap::new();
         class_catalog.insert("Repository", 1);
@@ -164,8 +183,10 @@ impl GraphvizRender {
 
 #[cfg(test)]
 mod tests {
+    use std::path::Path;
+
     use crate::render::graphviz_render::GraphvizRender;
-    use crate::{ClassInfo, ParseOption};
+    use crate::{ClassInfo, MemberInfo, ParseOption};
 
     #[test]
     fn should_render_graphviz() {
@@ -174,4 +195,56 @@ mod tests {
         let string = GraphvizRender::render(&clzs, &ParseOption::default());
         assert_eq!("digraph G {\n  compound=true;\n  ranksep=1\n  node[shape=record]\n\n  subgraph cluster_service{\n    WorldServiceImpl\n    }\n\n}", string);
     }
+
+    #[test]
+    fn should_return_data_of_visualization() {
+        let mut service = ClassInfo::new("OrderService");
+        let member = MemberInfo::new("repository", "-".to_string(), "OrderRepository".to_string());
+        service.members.push(member);
+        let mut repository = ClassInfo::new("OrderRepository");
+        repository.lang = "Java".to_string();
+        repository.package = "shop".to_string();
+        repository.file = "shop/OrderRepository.java".to_string();
+        repository.id = repository.stable_id(Path::new(""));
+        let clzs = vec![service, repository];
+
+        let (_, data) = GraphvizRender::render_with_data(&clzs, &ParseOption::default());
+
+        assert_eq!(2, data.nodes.len());
+        assert_eq!("OrderService", data.nodes[0].id);
+        assert_eq!(3, data.nodes[0].group);
+        assert_eq!(
+            "Java:shop.OrderRepository@shop/OrderRepository.java",
+            data.nodes[1].id
+        );
+        assert_eq!("OrderRepository", data.nodes[1].name);
+        assert_eq!(1, data.links.len());
+        assert_eq!("OrderService", data.links[0].source);
+        assert_eq!(data.nodes[1].id, data.links[0].target);
+    }
+
+    #[test]
+    fn should_keep_classes_of_same_name_apart() {
+        let order = |package: &str| {
+            let mut order = ClassInfo::new("Order");
+            order.lang = "Java".to_string();
+            order.package = package.to_string();
+            order.file = format!("{}/Order.java", package);
+            order.id = order.stable_id(Path::new(""));
+            order
+        };
+        let mut service = ClassInfo::new("OrderService");
+        let mut member = MemberInfo::new("order", "-".to_string(), "Order".to_string());
+        member.qualified_type = "shop.Order".to_string();
+        service.members.push(member);
+        let clzs = vec![service, order("shop"), order("legacy")];
+
+        let (_, data) = GraphvizRender::render_with_data(&clzs, &ParseOption::default());
+
+        assert_eq!("shop_Order", data.nodes[1].name);
+        assert_eq!("legacy_Order", data.nodes[2].name);
+        assert_ne!(data.nodes[1].id, data.nodes[2].id);
+        assert_eq!(1, data.links.len());
+        assert_eq!(data.nodes[1].id, data.links[0].target);
+    }
 }
@@ -1,5 +1,5 @@
 use crate::coco_struct::ClassInfo;
-use crate::render::{process_name, render_member, render_method};
+use crate::render::{process_name, render_member, render_method, ClassIndex};
 use crate::ParseOption;
 use std::collections::HashMap;
 
@@ -12,12 +12,13 @@ impl MermaidRender {
         let mut rendered: Vec<String> = vec![];
         let mut deps: Vec<String> = vec![];
 
+        let index = ClassIndex::new(classes, parse_option);
         let mut class_map: HashMap<String, bool> = HashMap::default();
         for clazz in classes {
             class_map.insert(process_name(&parse_option, &clazz.name), true);
         }
 
-        for clazz in classes {
+        for (clazz_index, clazz) in classes.iter().enumerate() {
             let mut dep_map: HashMap<String, String> = HashMap::default();
 
             let members = render_member(&clazz, &mut dep_map, space, parse_option, &mut class_map);
@@ -26,13 +27,23 @@ impl MermaidRender {
                 methods = render_method(&clazz, &mut dep_map, space, parse_option);
             }
 
-            let content = format!("{}{}", members.join(""), methods.join(""));
-            let class_name = process_name(&parse_option, &clazz.name);
+            let mut annotation = "".to_string();
+            if !clazz.annotations.is_empty() {
+                annotation = format!("{}  <<{}>>\n", space, clazz.annotations.join(", "));
+            }
+
+            let content = format!("{}{}{}", annotation, members.join(""), methods.join(""));
+            let class_name = index.label(clazz_index);
             if clazz.parents.len() > 0 && !parse_option.without_parent {
+                let parents: Vec<&str> = clazz
+                    .parents
+                    .iter()
+                    .map(|parent| index.label_of(parent))
+                    .collect();
                 rendered.push(format!(
                     "{}{} <|-- {}",
                     space,
-                    clazz.parents.join(","),
+                    parents.join(","),
                     class_name
                 ));
             }
@@ -42,16 +53,13 @@ impl MermaidRender {
                 space, class_name, content, space
             ));
 
-            for (callee, current_clz) in dep_map {
-                if callee == current_clz {
-                    continue;
-                }
-
-                if class_map.get(&callee).is_none() {
-                    continue;
-                }
-
-                deps.push(format!("{}{} -- {}\n", space, current_clz, callee));
+            for callee in index.deps(clazz_index, &dep_map) {
+                deps.push(format!(
+                    "{}{} -- {}\n",
+                    space,
+                    class_name,
+                    index.label(callee)
+                ));
             }
         }
 
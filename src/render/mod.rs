@@ -1,10 +1,14 @@
 use crate::{ClassInfo, ParseOption};
+pub use dependency_render::DependencyRender;
 pub use mermaid_render::MermaidRender;
+pub use module_render::ModuleRender;
 pub use plantuml_render::PlantUmlRender;
 use std::collections::HashMap;
 
+pub mod dependency_render;
 pub mod graphviz_render;
 pub mod mermaid_render;
+pub mod module_render;
 pub mod plantuml_render;
 
 pub fn process_name(parse_option: &&ParseOption, name: &str) -> String {
@@ -17,6 +21,97 @@ pub fn process_name(parse_option: &&ParseOption, name: &str) -> String {
     name.to_string()
 }
 
+/// Classes of a diagram by the keys of `dep_map`, which are qualified names of resolved types, or
+/// names of others. Classes are labeled by their names, or by qualified names if names are the
+/// same, like `com_a_Order` and `com_b_Order`.
+pub struct ClassIndex {
+    labels: Vec<String>,
+    by_qualified: HashMap<String, usize>,
+    by_name: HashMap<String, Vec<usize>>,
+}
+
+impl ClassIndex {
+    pub fn new(classes: &[ClassInfo], parse_option: &ParseOption) -> ClassIndex {
+        let mut by_qualified = HashMap::new();
+        let mut by_name: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, clazz) in classes.iter().enumerate() {
+            by_qualified.insert(clazz.qualified_name(), index);
+            by_name
+                .entry(process_name(&parse_option, &clazz.name))
+                .or_default()
+                .push(index);
+        }
+
+        let labels = classes
+            .iter()
+            .map(|clazz| {
+                let name = process_name(&parse_option, &clazz.name);
+                if by_name[&name].len() == 1 {
+                    return name;
+                }
+                let qualified = process_name(&parse_option, &clazz.qualified_name());
+                qualified
+                    .chars()
+                    .map(|c| if c.is_alphanumeric() { c } else { '_' })
+                    .collect()
+            })
+            .collect();
+
+        ClassIndex {
+            labels,
+            by_qualified,
+            by_name,
+        }
+    }
+
+    /// Returns the index of the class referred by `key`, a name refers to the only class of it.
+    pub fn find(&self, key: &str) -> Option<usize> {
+        if let Some(index) = self.by_qualified.get(key) {
+            return Some(*index);
+        }
+        match self.by_name.get(key).map(|indexes| indexes.as_slice()) {
+            Some([index]) => Some(*index),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self, index: usize) -> &str {
+        &self.labels[index]
+    }
+
+    /// Returns the label of class referred by `key`, or `key` itself if it's not in the diagram,
+    /// like parents out of the model.
+    pub fn label_of<'a>(&'a self, key: &'a str) -> &'a str {
+        match self.find(key) {
+            Some(index) => self.label(index),
+            None => key,
+        }
+    }
+
+    /// Returns indexes of classes in `dep_map` of the class at `index`, without itself.
+    pub fn deps(&self, index: usize, dep_map: &HashMap<String, String>) -> Vec<usize> {
+        let mut deps: Vec<usize> = dep_map
+            .keys()
+            .filter_map(|callee| self.find(callee))
+            .filter(|callee| *callee != index)
+            .collect();
+        deps.sort_unstable();
+        deps.dedup();
+        deps
+    }
+}
+
+/// Renders class annotations as PlantUML stereotypes, like ` <<Serialize>> <<Debug>>`
+pub fn render_stereotypes(clazz: &ClassInfo) -> String {
+    clazz
+        .annotations
+        .iter()
+        .map(|annotation| format!(" <<{}>>", annotation))
+        .collect::<Vec<String>>()
+        .join("")
+}
+
+/// Renders methods of `clazz`, their return types are added to `dep_map`, see `ClassIndex`.
 pub fn render_method(
     clazz: &ClassInfo,
     dep_map: &mut HashMap<String, String>,
@@ -24,18 +119,30 @@ pub fn render_method(
     parse_option: &ParseOption,
 ) -> Vec<String> {
     let mut methods = vec![];
-    let clazz_name = process_name(&parse_option, &clazz.name);
+    let clazz_name = clazz.qualified_name();
     for method in &clazz.methods {
         let method_name = process_name(&parse_option, &method.name);
+        let signature = if method.overloads > 1 {
+            format!("{}() [{} overloads]", method_name, method.overloads)
+        } else {
+            format!("{}()", method_name)
+        };
+
         if method.return_type.is_empty() {
-            methods.push(format!("{}  {}{}()\n", space, method.access, method_name))
+            methods.push(format!("{}  {}{}\n", space, method.access, signature))
         } else {
             methods.push(format!(
-                "{}  {} {} {}()\n",
-                space, method.access, method.return_type, method_name
+                "{}  {} {} {}\n",
+                space, method.access, method.return_type, signature
             ));
 
-            if method.pure_return_type.len() > 0 {
+            if method.external {
+                continue;
+            }
+
+            if !method.qualified_return_type.is_empty() {
+                dep_map.insert(method.qualified_return_type.clone(), clazz_name.clone());
+            } else if method.pure_return_type.len() > 0 {
                 dep_map.insert(method.pure_return_type.clone(), clazz_name.clone());
             } else {
                 dep_map.insert(method.return_type.clone(), clazz_name.clone());
@@ -45,6 +152,7 @@ pub fn render_method(
     methods
 }
 
+/// Renders members of `clazz`, their types are added to `dep_map`, see `ClassIndex`.
 pub fn render_member(
     clazz: &ClassInfo,
     dep_map: &mut HashMap<String, String>,
@@ -52,7 +160,7 @@ pub fn render_member(
     parse_option: &ParseOption,
     class_map: &mut HashMap<String, bool>,
 ) -> Vec<String> {
-    let clazz_name = process_name(&parse_option, &clazz.name);
+    let clazz_name = clazz.qualified_name();
     let mut members = vec![];
     for member in &clazz.members {
         let member_name = process_name(&parse_option, &member.name);
@@ -81,7 +189,15 @@ pub fn render_member(
                 space, member.access, data_type, member_name
             ));
 
-            if member.pure_data_type.len() > 0 {
+            // types renamed by options above are linked by their new name
+            let is_renamed = data_type != member.data_type;
+            if member.external && !is_renamed {
+                continue;
+            }
+
+            if !member.qualified_type.is_empty() && !is_renamed {
+                dep_map.insert(member.qualified_type.clone(), clazz_name.clone());
+            } else if member.pure_data_type.len() > 0 {
                 dep_map.insert(member.pure_data_type.clone(), clazz_name.clone());
             } else {
                 dep_map.insert(data_type.to_string(), clazz_name.clone());
@@ -105,7 +221,7 @@ fn remove_suffix_id(class_map: &mut HashMap<String, bool>, member_name: &String,
 #[cfg(test)]
 mod tests {
     use crate::coco_struct::{ClassInfo, MemberInfo, MethodInfo};
-    use crate::render::PlantUmlRender;
+    use crate::render::{MermaidRender, PlantUmlRender};
     use crate::ParseOption;
 
     #[test]
@@ -165,6 +281,49 @@ mod tests {
         assert_eq!(false, str.contains("Demo -- String"));
     }
 
+    #[test]
+    fn should_not_link_external_types() {
+        let mut demo = ClassInfo::new("Demo");
+        let list = ClassInfo::new("List");
+
+        let mut member = MemberInfo::new("items", "-".to_string(), "List".to_string());
+        member.external = true;
+        demo.members.push(member);
+
+        let mut method = MethodInfo::new("order", "-".to_string(), vec![], "Order".to_string());
+        method.qualified_return_type = "com.b.Order".to_string();
+        demo.methods.push(method);
+
+        let mut order = ClassInfo::new("Order");
+        order.package = "com.b".to_string();
+        let classes = vec![demo, list, order];
+        let str = PlantUmlRender::render(&classes, &ParseOption::default());
+        assert!(!str.contains("Demo -- List"));
+        assert!(str.contains("Demo -- Order"));
+    }
+
+    #[test]
+    fn should_link_qualified_types_of_same_name() {
+        let mut demo = ClassInfo::new("Demo");
+        let mut method = MethodInfo::new("order", "-".to_string(), vec![], "Order".to_string());
+        method.qualified_return_type = "com.b.Order".to_string();
+        demo.methods.push(method);
+        let mut order_a = ClassInfo::new("Order");
+        order_a.package = "com.a".to_string();
+        let mut order_b = ClassInfo::new("Order");
+        order_b.package = "com.b".to_string();
+
+        let classes = vec![demo, order_a, order_b];
+        let str = PlantUmlRender::render(&classes, &ParseOption::default());
+        assert!(str.contains("class com_a_Order {"));
+        assert!(str.contains("Demo -- com_b_Order"));
+        assert!(!str.contains("Demo -- com_a_Order"));
+
+        let str = MermaidRender::render(&classes, &ParseOption::default());
+        assert!(str.contains("Demo -- com_b_Order"));
+        assert!(!str.contains("Demo -- com_a_Order"));
+    }
+
     #[test]
     fn should_render_parents() {
         let mut classes = vec![];
@@ -220,6 +379,30 @@ mod tests {
         assert_eq!(false, str.contains("Demo -- String"));
     }
 
+    #[test]
+    fn should_render_annotations_as_stereotypes() {
+        let mut demo = ClassInfo::new("Demo");
+        demo.annotations = vec!["Serialize".to_string(), "Debug".to_string()];
+        let classes = vec![demo];
+
+        let str = PlantUmlRender::render(&classes, &ParseOption::default());
+        assert!(str.contains("class Demo <<Serialize>> <<Debug>> {"));
+
+        let str = MermaidRender::render(&classes, &ParseOption::default());
+        assert!(str.contains("class Demo {\n      <<Serialize, Debug>>\n"));
+    }
+
+    #[test]
+    fn should_render_overload_count() {
+        let mut demo = ClassInfo::new("Demo");
+        let mut method = MethodInfo::new("save", "+".to_string(), vec![], "".to_string());
+        method.overloads = 2;
+        demo.methods.push(method);
+
+        let str = PlantUmlRender::render(&vec![demo], &ParseOption::default());
+        assert!(str.contains("  +save() [2 overloads]\n"));
+    }
+
     #[test]
     fn should_char() {
         let str = "IRepo";
@@ -0,0 +1,145 @@
+use crate::workspace::ModuleGraph;
+
+/// Render dependencies between modules to string, edges are labeled by their weight
+pub struct ModuleRender;
+
+impl ModuleRender {
+    pub fn plantuml(graph: &ModuleGraph) -> String {
+        let mut rendered = vec![];
+        for (index, name) in graph.modules.keys().enumerate() {
+            rendered.push(format!("component \"{}\" as m{}", name, index));
+        }
+
+        let mut deps = vec![];
+        for edge in &graph.edges {
+            deps.push(format!(
+                "{} --> {} : {}\n",
+                Self::id(graph, &edge.source),
+                Self::id(graph, &edge.target),
+                edge.weight
+            ));
+        }
+
+        format!(
+            "@startuml\n\n{}\n\n{}\n@enduml",
+            rendered.join("\n"),
+            deps.join("")
+        )
+    }
+
+    pub fn mermaid(graph: &ModuleGraph) -> String {
+        let space = "    ";
+        let mut rendered = vec![];
+        for (index, name) in graph.modules.keys().enumerate() {
+            rendered.push(format!("{}m{}[\"{}\"]\n", space, index, name));
+        }
+
+        let mut deps = vec![];
+        for edge in &graph.edges {
+            deps.push(format!(
+                "{}{} -->|{}| {}\n",
+                space,
+                Self::id(graph, &edge.source),
+                edge.weight,
+                Self::id(graph, &edge.target)
+            ));
+        }
+
+        format!("graph LR\n{}{}", rendered.join(""), deps.join(""))
+    }
+
+    /// Modules are clusters of their packages, edges are drawn between the clusters.
+    pub fn graphviz(graph: &ModuleGraph) -> String {
+        let mut clusters = vec![];
+        for (index, (name, packages)) in graph.modules.iter().enumerate() {
+            let mut nodes = vec![];
+            for (package_index, package) in packages.iter().enumerate() {
+                let label = if package.is_empty() { name } else { package };
+                nodes.push(format!(
+                    "    m{}_{} [label=\"{}\"]\n",
+                    index, package_index, label
+                ));
+            }
+
+            clusters.push(format!(
+                "  subgraph cluster_m{} {{\n    label=\"{}\"\n{}  }}\n",
+                index,
+                name,
+                nodes.join("")
+            ));
+        }
+
+        let mut deps = vec![];
+        for edge in &graph.edges {
+            let source = Self::id(graph, &edge.source);
+            let target = Self::id(graph, &edge.target);
+            deps.push(format!(
+                "  {}_0 -> {}_0 [ltail=cluster_{}, lhead=cluster_{}, label=\"{}\", weight={}]\n",
+                source, target, source, target, edge.weight, edge.weight
+            ));
+        }
+
+        format!(
+            "digraph G {{\n  compound=true\n  rankdir=LR\n  node[shape=box]\n{}{}}}",
+            clusters.join(""),
+            deps.join("")
+        )
+    }
+
+    /// modules are numbered by name, like the nodes of `DependencyRender`
+    fn id(graph: &ModuleGraph, module: &str) -> String {
+        let index = graph
+            .modules
+            .keys()
+            .position(|name| name == module)
+            .unwrap_or_default();
+        format!("m{}", index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::render::ModuleRender;
+    use crate::source::dependency::DependencyEdge;
+    use crate::workspace::ModuleGraph;
+
+    fn graph() -> ModuleGraph {
+        let mut graph = ModuleGraph::default();
+        graph
+            .modules
+            .insert("order".to_string(), vec!["shop.order".to_string()]);
+        graph.modules.insert(
+            "web".to_string(),
+            vec!["shop.web".to_string(), "shop.web.api".to_string()],
+        );
+        graph.edges.push(DependencyEdge {
+            source: "web".to_string(),
+            target: "order".to_string(),
+            weight: 3,
+        });
+        graph
+    }
+
+    #[test]
+    fn should_render_modules_as_components() {
+        assert_eq!(
+            "@startuml\n\ncomponent \"order\" as m0\ncomponent \"web\" as m1\n\nm1 --> m0 : 3\n\n@enduml",
+            ModuleRender::plantuml(&graph())
+        );
+        assert_eq!(
+            "graph LR\n    m0[\"order\"]\n    m1[\"web\"]\n    m1 -->|3| m0\n",
+            ModuleRender::mermaid(&graph())
+        );
+    }
+
+    #[test]
+    fn should_render_modules_as_graphviz_clusters() {
+        let dot = ModuleRender::graphviz(&graph());
+
+        assert!(dot.contains("compound=true"));
+        assert!(dot.contains("  subgraph cluster_m1 {\n    label=\"web\"\n    m1_0 [label=\"shop.web\"]\n    m1_1 [label=\"shop.web.api\"]\n  }\n"));
+        assert!(dot.contains(
+            "  m1_0 -> m0_0 [ltail=cluster_m1, lhead=cluster_m0, label=\"3\", weight=3]\n"
+        ));
+    }
+}
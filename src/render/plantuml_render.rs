@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use crate::coco_struct::ClassInfo;
-use crate::render::{process_name, render_member, render_method};
+use crate::render::{process_name, render_member, render_method, render_stereotypes, ClassIndex};
 use crate::ParseOption;
 
 /// Render classes info to string
@@ -12,12 +12,13 @@ impl PlantUmlRender {
         let mut rendered: Vec<String> = vec![];
         let mut deps: Vec<String> = vec![];
 
+        let index = ClassIndex::new(classes, parse_option);
         let mut class_map: HashMap<String, bool> = HashMap::default();
         for clazz in classes {
             class_map.insert(process_name(&parse_option, &clazz.name), true);
         }
 
-        for clazz in classes {
+        for (clazz_index, clazz) in classes.iter().enumerate() {
             let mut dep_map: HashMap<String, String> = HashMap::default();
 
             let members = render_member(&clazz, &mut dep_map, "", parse_option, &mut class_map);
@@ -27,25 +28,22 @@ impl PlantUmlRender {
             }
 
             let content = format!("{}{}", members.join(""), methods.join(""));
-            let clazz_name = process_name(&parse_option, &clazz.name);
+            let clazz_name = index.label(clazz_index);
             if clazz.parents.len() > 0 && !parse_option.without_parent {
                 for parent in &clazz.parents {
-                    rendered.push(format!("{} <|-- {}", parent, clazz.name));
+                    rendered.push(format!("{} <|-- {}", index.label_of(parent), clazz_name));
                 }
             }
 
-            rendered.push(format!("class {} {{\n{}}}", clazz_name, content));
-
-            for (callee, current_clz) in dep_map {
-                if callee == current_clz {
-                    continue;
-                }
-
-                if class_map.get(&callee).is_none() {
-                    continue;
-                }
+            rendered.push(format!(
+                "class {}{} {{\n{}}}",
+                clazz_name,
+                render_stereotypes(clazz),
+                content
+            ));
 
-                deps.push(format!("{} -- {}\n", current_clz, callee));
+            for callee in index.deps(clazz_index, &dep_map) {
+                deps.push(format!("{} -- {}\n", clazz_name, index.label(callee)));
             }
         }
 
@@ -0,0 +1,328 @@
+use regex::Regex;
+
+lazy_static! {
+    static ref AT_ANNOTATION: Regex = Regex::new(r"@(?P<name>[A-Za-z_][A-Za-z0-9_.]*)").unwrap();
+}
+
+/// keywords which declare a class before its name, like `class Order` or `data class Order`
+const CLASS_KEYWORDS: [&str; 10] = [
+    "class",
+    "struct",
+    "interface",
+    "enum",
+    "record",
+    "object",
+    "trait",
+    "union",
+    "type",
+    "message",
+];
+
+/// Returns annotations declared for the class at `line_no` (1-based) of `lines`.
+///
+/// * Rust: every `#[derive(...)]` item, and the name of other attributes, like `serde`
+/// * C#: attributes, like `[Serializable]`
+/// * Java, Kotlin, TypeScript, Python...: annotations or decorators, like `@Entity`
+pub fn annotations_of(lang: &str, class_name: &str, lines: &[&str], line_no: usize) -> Vec<String> {
+    if line_no == 0 || line_no > lines.len() {
+        return vec![];
+    }
+
+    let declaration = lines[line_no - 1];
+    let same_line = match declared_at(declaration, class_name) {
+        Some(index) => &declaration[..index],
+        None => "",
+    };
+    let block = format!("{} {}", attribute_block(&lines[..line_no - 1]), same_line);
+
+    let annotations = match lang {
+        "Rust" => rust_attributes(&block),
+        "C#" => csharp_attributes(&block),
+        "C" | "C++" | "Go" => vec![],
+        _ => AT_ANNOTATION
+            .captures_iter(&block)
+            .map(|capts| last_segment(&capts["name"], '.'))
+            .collect(),
+    };
+
+    let mut unique: Vec<String> = vec![];
+    for annotation in annotations {
+        if !annotation.is_empty() && !unique.contains(&annotation) {
+            unique.push(annotation);
+        }
+    }
+    unique
+}
+
+/// Returns where `class_name` is declared in `line`: the whole word after a class keyword, or
+/// the first whole word without a keyword, so `Order` of `@OrderAudited class Order` is the
+/// one after `class`.
+fn declared_at(line: &str, class_name: &str) -> Option<usize> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let words: Vec<usize> = line
+        .match_indices(class_name)
+        .map(|(index, _)| index)
+        .filter(|index| {
+            let before = line[..*index].chars().next_back();
+            let after = line[index + class_name.len()..].chars().next();
+            !before.is_some_and(is_word) && !after.is_some_and(is_word)
+        })
+        .collect();
+
+    let after_keyword = words.iter().find(|index| {
+        let before = line[..**index].trim_end();
+        CLASS_KEYWORDS.iter().any(|keyword| {
+            before
+                .strip_suffix(keyword)
+                .is_some_and(|rest| !rest.ends_with(is_word))
+        })
+    });
+    after_keyword.or_else(|| words.first()).copied()
+}
+
+/// Collects attribute lines directly above a declaration, skipping comments and blank lines.
+fn attribute_block(lines: &[&str]) -> String {
+    let mut block: Vec<&str> = vec![];
+    // brackets closed by lines below which are still waiting for their opening line
+    let mut depth: i32 = 0;
+    for line in lines.iter().rev() {
+        let trimmed = line.trim();
+        let balance =
+            trimmed.matches([']', ')']).count() as i32 - trimmed.matches(['[', '(']).count() as i32;
+
+        if depth > 0 {
+            block.push(trimmed);
+            depth = std::cmp::max(0, depth + balance);
+            continue;
+        }
+
+        if trimmed.is_empty() || is_comment(trimmed) {
+            continue;
+        }
+
+        let is_attribute = trimmed.starts_with("#[")
+            || trimmed.starts_with('@')
+            || trimmed.starts_with('[')
+            || trimmed.ends_with(']')
+            || trimmed.ends_with(')');
+        if !is_attribute {
+            break;
+        }
+
+        block.push(trimmed);
+        depth = std::cmp::max(0, balance);
+    }
+
+    block.reverse();
+    block.join(" ")
+}
+
+fn is_comment(line: &str) -> bool {
+    line.starts_with("//")
+        || line.starts_with("/*")
+        || line.starts_with('*')
+        || (line.starts_with('#') && !line.starts_with("#["))
+}
+
+fn rust_attributes(block: &str) -> Vec<String> {
+    let mut attributes = vec![];
+    for content in bracket_contents(block, "#[") {
+        if let Some(derives) = content.strip_prefix("derive") {
+            let derives = derives.trim().trim_start_matches('(').trim_end_matches(')');
+            for derive in derives.split(',') {
+                attributes.push(last_segment(derive.trim(), ':'));
+            }
+            continue;
+        }
+
+        let name = attribute_name(&content);
+        if name != "doc" {
+            attributes.push(last_segment(&name, ':'));
+        }
+    }
+    attributes
+}
+
+fn csharp_attributes(block: &str) -> Vec<String> {
+    let mut attributes = vec![];
+    for content in bracket_contents(block, "[") {
+        for attribute in split_top_level(&content) {
+            attributes.push(last_segment(&attribute_name(&attribute), '.'));
+        }
+    }
+    attributes
+}
+
+/// Returns the text between `open` and its matching `]`, for every occurrence in `text`.
+fn bracket_contents(text: &str, open: &str) -> Vec<String> {
+    let mut contents = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.find(open) {
+        let after = &rest[start + open.len()..];
+        let mut depth = 1;
+        let mut end = None;
+        for (index, c) in after.char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = Some(index);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        match end {
+            Some(end) => {
+                contents.push(after[..end].trim().to_string());
+                rest = &after[end + 1..];
+            }
+            None => break,
+        }
+    }
+    contents
+}
+
+fn split_top_level(text: &str) -> Vec<String> {
+    let mut items = vec![];
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in text.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    items.push(current.trim().to_string());
+    items
+}
+
+fn attribute_name(content: &str) -> String {
+    content
+        .split(|c: char| c == '(' || c == '=' || c.is_whitespace())
+        .next()
+        .unwrap_or("")
+        .to_string()
+}
+
+fn last_segment(name: &str, separator: char) -> String {
+    name.rsplit(separator)
+        .next()
+        .unwrap_or(name)
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::source::annotation::annotations_of;
+
+    fn lines(code: &str) -> Vec<&str> {
+        code.lines().collect()
+    }
+
+    #[test]
+    fn should_collect_rust_derives_and_attributes() {
+        let code = lines(
+            "use serde::{Deserialize, Serialize};
+
+/// Parse option
+#[derive(Serialize, serde::Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ParseOption {",
+        );
+
+        assert_eq!(
+            vec![
+                "Serialize",
+                "Deserialize",
+                "Debug",
+                "Clone",
+                "Default",
+                "serde"
+            ],
+            annotations_of("Rust", "ParseOption", &code, 6)
+        );
+    }
+
+    #[test]
+    fn should_collect_multiline_rust_derive() {
+        let code = lines(
+            "}
+
+#[derive(
+    Debug,
+    Clone,
+)]
+struct Demo {",
+        );
+
+        assert_eq!(
+            vec!["Debug", "Clone"],
+            annotations_of("Rust", "Demo", &code, 7)
+        );
+    }
+
+    #[test]
+    fn should_stop_at_previous_item() {
+        let code = lines(
+            "#[derive(Debug)]
+struct First;
+struct Second {",
+        );
+
+        assert!(annotations_of("Rust", "Second", &code, 3).is_empty());
+    }
+
+    #[test]
+    fn should_collect_java_annotations() {
+        let code = lines(
+            "import javax.persistence.Entity;
+
+/**
+ * an order
+ */
+@Entity
+@Table(name = \"orders\")
+@lombok.Data public class Order {",
+        );
+
+        assert_eq!(
+            vec!["Entity", "Table", "Data"],
+            annotations_of("Java", "Order", &code, 8)
+        );
+    }
+
+    #[test]
+    fn should_collect_csharp_attributes() {
+        let code = lines(
+            "[Serializable, Table(\"orders\")]
+public class Order {",
+        );
+
+        assert_eq!(
+            vec!["Serializable", "Table"],
+            annotations_of("C#", "Order", &code, 2)
+        );
+    }
+
+    #[test]
+    fn should_find_class_after_its_keyword() {
+        let code = lines("@OrderAudited @Entity class Order {");
+
+        assert_eq!(
+            vec!["OrderAudited", "Entity"],
+            annotations_of("Java", "Order", &code, 1)
+        );
+    }
+}
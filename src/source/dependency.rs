@@ -0,0 +1,493 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Component, Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ctags::rust_module::CrateRoots;
+use crate::source::imports::{
+    directory_of, lang_by_extension, parse_imports_in, FileImports, ImportInfo,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct DependencyEdge {
+    pub source: String,
+    pub target: String,
+    /// count of imports behind this edge
+    pub weight: usize,
+}
+
+/// File to file and package to package dependencies, built from import statements.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DependencyGraph {
+    pub files: Vec<FileImports>,
+    pub file_edges: Vec<DependencyEdge>,
+    pub package_edges: Vec<DependencyEdge>,
+}
+
+impl DependencyGraph {
+    /// Builds the graph from `files`, which are read relative to `root`.
+    pub fn build<P: AsRef<Path>>(root: P, files: &[String]) -> DependencyGraph {
+        let crate_roots = CrateRoots::find(&root, files);
+        let mut imports = vec![];
+        for file in files {
+            let lang = match lang_by_extension(file) {
+                Some(lang) => lang,
+                None => continue,
+            };
+
+            if let Ok(content) = fs::read_to_string(root.as_ref().join(file)) {
+                imports.push(parse_imports_in(&crate_roots, file, lang, &content));
+            }
+        }
+
+        DependencyGraph::from_imports(imports)
+    }
+
+    /// Resolves imports to files of the project, imports of outside files are dropped.
+    pub fn from_imports(files: Vec<FileImports>) -> DependencyGraph {
+        let resolver = ImportResolver::new(&files);
+
+        let mut file_weights: BTreeMap<(String, String), usize> = BTreeMap::new();
+        for file in &files {
+            for import in &file.imports {
+                for target in resolver.resolve(file, import) {
+                    if target != file.file {
+                        *file_weights
+                            .entry((file.file.clone(), target.to_string()))
+                            .or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+
+        let packages: HashMap<&str, &str> = files
+            .iter()
+            .map(|file| (file.file.as_str(), file.package.as_str()))
+            .collect();
+        let mut package_weights: BTreeMap<(String, String), usize> = BTreeMap::new();
+        for ((source, target), weight) in &file_weights {
+            let source = packages[source.as_str()];
+            let target = packages[target.as_str()];
+            if source != target {
+                *package_weights
+                    .entry((source.to_string(), target.to_string()))
+                    .or_insert(0) += weight;
+            }
+        }
+
+        DependencyGraph {
+            file_edges: to_edges(file_weights),
+            package_edges: to_edges(package_weights),
+            files,
+        }
+    }
+
+    pub fn file_of(&self, file: &str) -> Option<&FileImports> {
+        self.files.iter().find(|imports| imports.file == file)
+    }
+}
+
+fn to_edges(weights: BTreeMap<(String, String), usize>) -> Vec<DependencyEdge> {
+    weights
+        .into_iter()
+        .map(|((source, target), weight)| DependencyEdge {
+            source,
+            target,
+            weight,
+        })
+        .collect()
+}
+
+const SCRIPT_SUFFIXES: [&str; 9] = [
+    "",
+    ".ts",
+    ".tsx",
+    ".d.ts",
+    ".js",
+    ".jsx",
+    ".mjs",
+    "/index.ts",
+    "/index.js",
+];
+
+/// Finds the files of the project which an import refers to.
+pub(crate) struct ImportResolver<'a> {
+    by_path: HashMap<String, &'a str>,
+    by_file_name: HashMap<String, Vec<&'a str>>,
+    /// `package.FileStem` for Java and Kotlin, module path for Rust
+    by_qualified: HashMap<String, Vec<&'a str>>,
+    by_package: HashMap<String, Vec<&'a str>>,
+}
+
+impl<'a> ImportResolver<'a> {
+    pub(crate) fn new(files: &'a [FileImports]) -> ImportResolver<'a> {
+        let mut resolver = ImportResolver {
+            by_path: HashMap::default(),
+            by_file_name: HashMap::default(),
+            by_qualified: HashMap::default(),
+            by_package: HashMap::default(),
+        };
+
+        for file in files {
+            let name = file.file.as_str();
+            let path = Path::new(name);
+            resolver.by_path.insert(normalize(name), name);
+            if let Some(file_name) = path.file_name().and_then(|s| s.to_str()) {
+                resolver
+                    .by_file_name
+                    .entry(file_name.to_string())
+                    .or_default()
+                    .push(name);
+            }
+
+            let qualified = match file.lang.as_str() {
+                "Java" | "Kotlin" => {
+                    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+                    if file.package.is_empty() {
+                        stem.to_string()
+                    } else {
+                        format!("{}.{}", file.package, stem)
+                    }
+                }
+                "Rust" => file.package.clone(),
+                _ => "".to_string(),
+            };
+            if !qualified.is_empty() {
+                resolver
+                    .by_qualified
+                    .entry(qualified)
+                    .or_default()
+                    .push(name);
+            }
+
+            resolver
+                .by_package
+                .entry(file.package.clone())
+                .or_default()
+                .push(name);
+        }
+
+        resolver
+    }
+
+    pub(crate) fn resolve(&self, file: &FileImports, import: &ImportInfo) -> Vec<&'a str> {
+        match file.lang.as_str() {
+            "Java" | "Kotlin" => self.resolve_java(file, import),
+            "TypeScript" | "JavaScript" => {
+                self.resolve_relative_path(file, &import.source, &SCRIPT_SUFFIXES)
+            }
+            "Go" => self.resolve_go(file, import),
+            "Rust" => self.resolve_rust(file, import),
+            "C" | "C++" => {
+                let found = self.resolve_relative_path(file, &import.source, &[""]);
+                if !found.is_empty() {
+                    return found;
+                }
+                self.resolve_by_suffix(file, &import.source)
+            }
+            _ => vec![],
+        }
+    }
+
+    fn resolve_java(&self, file: &FileImports, import: &ImportInfo) -> Vec<&'a str> {
+        if let Some(package) = import.source.strip_suffix(".*") {
+            return self.by_package.get(package).cloned().unwrap_or_default();
+        }
+
+        // the last segment of static imports and nested classes isn't a file
+        let mut qualified = import.source.as_str();
+        loop {
+            if let Some(found) = self.by_qualified.get(qualified) {
+                return vec![closest(&file.file, found)];
+            }
+
+            match qualified.rsplit_once('.') {
+                Some((parent, _)) => qualified = parent,
+                None => return vec![],
+            }
+        }
+    }
+
+    fn resolve_go(&self, file: &FileImports, import: &ImportInfo) -> Vec<&'a str> {
+        let mut best: Option<&String> = None;
+        for package in self.by_package.keys() {
+            if package.is_empty() || package == &file.package {
+                continue;
+            }
+
+            let is_match =
+                import.source == *package || import.source.ends_with(&format!("/{}", package));
+            if is_match && best.is_none_or(|best| package.len() > best.len()) {
+                best = Some(package);
+            }
+        }
+
+        match best {
+            Some(package) => self.by_package[package]
+                .iter()
+                .filter(|name| name.ends_with(".go"))
+                .cloned()
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    fn resolve_rust(&self, file: &FileImports, import: &ImportInfo) -> Vec<&'a str> {
+        let mut path = import.source.clone();
+        if let Some(name) = import.names.first() {
+            path = format!("{}::{}", path, name);
+        }
+
+        let mut segments: Vec<&str> = path.split("::").collect();
+        let mut module: Vec<&str> = file.package.split("::").collect();
+        match segments.first() {
+            Some(&"self") => {
+                segments.remove(0);
+            }
+            Some(&"super") => {
+                while segments.first() == Some(&"super") {
+                    segments.remove(0);
+                    module.pop();
+                }
+            }
+            Some(&"crate") => {
+                module.clear();
+            }
+            // paths of child modules, like `pub use file_filter::*` in `lib.rs`, others are crates
+            Some(child) => {
+                let child = format!("{}::{}", file.package, child);
+                if !self.by_qualified.contains_key(&child) {
+                    return vec![];
+                }
+            }
+            None => return vec![],
+        }
+        module.extend(segments);
+
+        while !module.is_empty() {
+            if let Some(found) = self.by_qualified.get(&module.join("::")) {
+                return vec![closest(&file.file, found)];
+            }
+            module.pop();
+        }
+        vec![]
+    }
+
+    fn resolve_relative_path(
+        &self,
+        file: &FileImports,
+        source: &str,
+        suffixes: &[&str],
+    ) -> Vec<&'a str> {
+        if source.starts_with('/') || !(source.starts_with('.') || file.lang.starts_with('C')) {
+            return vec![];
+        }
+
+        let base = normalize(&format!("{}/{}", directory_of(&file.file), source));
+        for suffix in suffixes {
+            if let Some(found) = self.by_path.get(&format!("{}{}", base, suffix)) {
+                return vec![found];
+            }
+        }
+        vec![]
+    }
+
+    fn resolve_by_suffix(&self, file: &FileImports, source: &str) -> Vec<&'a str> {
+        let file_name = match Path::new(source).file_name().and_then(|s| s.to_str()) {
+            Some(file_name) => file_name,
+            None => return vec![],
+        };
+
+        let suffix = normalize(source);
+        let candidates: Vec<&'a str> = self
+            .by_file_name
+            .get(file_name)
+            .map(|files| {
+                files
+                    .iter()
+                    .filter(|name| {
+                        let name = normalize(name);
+                        name == suffix || name.ends_with(&format!("/{}", suffix))
+                    })
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if candidates.is_empty() {
+            return vec![];
+        }
+        vec![closest(&file.file, &candidates)]
+    }
+}
+
+/// Picks the candidate sharing the longest directory prefix with `file`.
+fn closest<'a>(file: &str, candidates: &[&'a str]) -> &'a str {
+    let common = |candidate: &str| {
+        Path::new(file)
+            .components()
+            .zip(Path::new(candidate).components())
+            .take_while(|(a, b)| a == b)
+            .count()
+    };
+
+    let mut best = candidates[0];
+    for candidate in candidates.iter().skip(1) {
+        if common(candidate) > common(best) {
+            best = candidate;
+        }
+    }
+    best
+}
+
+/// Normalizes `./a/../b.ts` to `b.ts`, with `/` as separator.
+pub(crate) fn normalize(path: &str) -> String {
+    let mut parts: Vec<String> = vec![];
+    let mut absolute = false;
+    for component in Path::new(path).components() {
+        match component {
+            Component::RootDir => absolute = true,
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if parts.pop().is_none() {
+                    parts.push("..".to_string());
+                }
+            }
+            Component::Normal(name) => parts.push(name.to_string_lossy().to_string()),
+            Component::Prefix(prefix) => {
+                parts.push(prefix.as_os_str().to_string_lossy().to_string())
+            }
+        }
+    }
+
+    let joined = parts.join("/");
+    if absolute {
+        format!("/{}", joined)
+    } else {
+        joined
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::source::dependency::{normalize, DependencyEdge, DependencyGraph};
+    use crate::source::imports::parse_imports;
+
+    fn edge(source: &str, target: &str, weight: usize) -> DependencyEdge {
+        DependencyEdge {
+            source: source.to_string(),
+            target: target.to_string(),
+            weight,
+        }
+    }
+
+    #[test]
+    fn should_normalize_path() {
+        assert_eq!("src/zoo/snake", normalize("./src/app/../zoo/snake"));
+        assert_eq!("/root/a.ts", normalize("/root/./a.ts"));
+    }
+
+    #[test]
+    fn should_build_java_file_and_package_edges() {
+        let files = vec![
+            parse_imports(
+                "src/order/Order.java",
+                "Java",
+                "package com.shop.order;\nimport com.shop.user.User;\nimport com.shop.user.*;\nimport java.util.List;",
+            ),
+            parse_imports("src/user/User.java", "Java", "package com.shop.user;"),
+            parse_imports("src/user/Role.java", "Java", "package com.shop.user;"),
+        ];
+
+        let graph = DependencyGraph::from_imports(files);
+        assert_eq!(
+            vec![
+                edge("src/order/Order.java", "src/user/Role.java", 1),
+                edge("src/order/Order.java", "src/user/User.java", 2),
+            ],
+            graph.file_edges
+        );
+        assert_eq!(
+            vec![edge("com.shop.order", "com.shop.user", 3)],
+            graph.package_edges
+        );
+    }
+
+    #[test]
+    fn should_resolve_type_script_relative_imports() {
+        let files = vec![
+            parse_imports(
+                "./src/app/main.ts",
+                "TypeScript",
+                "import { Snake } from '../zoo';\nimport x from 'lodash';",
+            ),
+            parse_imports("./src/zoo/index.ts", "TypeScript", ""),
+        ];
+
+        let graph = DependencyGraph::from_imports(files);
+        assert_eq!(
+            vec![edge("./src/app/main.ts", "./src/zoo/index.ts", 1)],
+            graph.file_edges
+        );
+        assert_eq!(vec![edge("src/app", "src/zoo", 1)], graph.package_edges);
+    }
+
+    #[test]
+    fn should_resolve_go_import_path_to_directory() {
+        let files = vec![
+            parse_imports(
+                "cmd/main.go",
+                "Go",
+                "package main\nimport \"github.com/inherd/app/pkg/store\"",
+            ),
+            parse_imports("pkg/store/store.go", "Go", "package store"),
+        ];
+
+        let graph = DependencyGraph::from_imports(files);
+        assert_eq!(vec![edge("cmd", "pkg/store", 1)], graph.package_edges);
+    }
+
+    #[test]
+    fn should_resolve_rust_use_to_module_file() {
+        let files = vec![
+            parse_imports(
+                "src/lib.rs",
+                "Rust",
+                "use crate::render::{PlantUmlRender, graphviz_render::GraphvizRender};\nuse std::fs;\npub use coco_struct::*;",
+            ),
+            parse_imports("src/render/mod.rs", "Rust", "use super::coco_struct::ClassInfo;"),
+            parse_imports("src/render/graphviz_render.rs", "Rust", ""),
+            parse_imports("src/coco_struct.rs", "Rust", ""),
+        ];
+
+        let graph = DependencyGraph::from_imports(files);
+        assert_eq!(
+            vec![
+                edge("src/lib.rs", "src/coco_struct.rs", 1),
+                edge("src/lib.rs", "src/render/graphviz_render.rs", 1),
+                edge("src/lib.rs", "src/render/mod.rs", 1),
+                edge("src/render/mod.rs", "src/coco_struct.rs", 1),
+            ],
+            graph.file_edges
+        );
+    }
+
+    #[test]
+    fn should_resolve_c_include_by_suffix() {
+        let files = vec![
+            parse_imports(
+                "src/main.cpp",
+                "C++",
+                "#include <iostream>\n#include \"orm/field.h\"",
+            ),
+            parse_imports("include/orm/field.h", "C++", ""),
+        ];
+
+        let graph = DependencyGraph::from_imports(files);
+        assert_eq!(
+            vec![edge("src/main.cpp", "include/orm/field.h", 1)],
+            graph.file_edges
+        );
+    }
+}
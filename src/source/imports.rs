@@ -0,0 +1,478 @@
+use std::ops::Range;
+use std::path::Path;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::ctags::rust_module::CrateRoots;
+
+/// Imports and declared package of a source file
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FileImports {
+    pub file: String,
+    pub lang: String,
+    /// package of the file: Java/Kotlin `package`, Rust module path, or the directory for others
+    pub package: String,
+    pub imports: Vec<ImportInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ImportInfo {
+    /// imported path as written, like `com.phodal.Order`, `./animal` or `crate::render`
+    pub source: String,
+    /// imported names, like `A` and `B` for `import { A, B } from './a'`
+    pub names: Vec<String>,
+    /// `#include <...>` in C/C++, which is usually outside of the project
+    pub system: bool,
+    pub line_no: i32,
+}
+
+impl ImportInfo {
+    pub fn new(source: &str, line_no: i32) -> Self {
+        ImportInfo {
+            source: source.to_string(),
+            names: vec![],
+            system: false,
+            line_no,
+        }
+    }
+}
+
+lazy_static! {
+    static ref JAVA_PACKAGE: Regex =
+        Regex::new(r"(?m)^[ \t]*package\s+(?P<package>[A-Za-z0-9_.]+)\s*;?").unwrap();
+    static ref JAVA_IMPORT: Regex =
+        Regex::new(r"(?m)^[ \t]*import\s+(static\s+)?(?P<source>[A-Za-z0-9_]+(\.[A-Za-z0-9_]+)*(\.\*)?)\s*;?").unwrap();
+    static ref TS_IMPORT: Regex = Regex::new(
+        r#"(?s)\b(import|export)\s+(type\s+)?(?P<names>[^;'"]*?)\s*from\s*['"](?P<source>[^'"]+)['"]"#
+    )
+    .unwrap();
+    static ref TS_SIDE_EFFECT_IMPORT: Regex =
+        Regex::new(r#"(?m)^[ \t]*import\s*['"](?P<source>[^'"]+)['"]"#).unwrap();
+    static ref TS_REQUIRE: Regex =
+        Regex::new(r#"\brequire\(\s*['"](?P<source>[^'"]+)['"]\s*\)"#).unwrap();
+    static ref GO_IMPORT_BLOCK: Regex =
+        Regex::new(r"(?s)\bimport\s*\((?P<block>.*?)\)").unwrap();
+    static ref GO_IMPORT: Regex =
+        Regex::new(r#"(?m)^[ \t]*import\s+([A-Za-z0-9_.]+\s+)?"(?P<source>[^"]+)""#).unwrap();
+    static ref GO_IMPORT_SPEC: Regex =
+        Regex::new(r#"(?m)^[ \t]*([A-Za-z0-9_.]+\s+)?"(?P<source>[^"]+)""#).unwrap();
+    static ref RUST_USE: Regex =
+        Regex::new(r"(?s)(^|\n)\s*(pub(\([a-z ]+\))?\s+)?use\s+(?P<tree>[^;]+);").unwrap();
+    static ref RUST_INLINE_MOD: Regex =
+        Regex::new(r"(?m)^[ \t]*(pub(\([a-z ]+\))?\s+)?mod\s+[A-Za-z0-9_]+\s*\{").unwrap();
+    static ref C_INCLUDE: Regex =
+        Regex::new(r#"(?m)^[ \t]*#\s*include\s*(?P<open>[<"])(?P<source>[^>"]+)[>"]"#).unwrap();
+}
+
+/// Returns the language of a source file by its extension, with ctags' language names.
+pub fn lang_by_extension(file: &str) -> Option<&'static str> {
+    let extension = Path::new(file).extension()?.to_str()?;
+    let lang = match extension {
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "ts" | "tsx" => "TypeScript",
+        "js" | "jsx" | "mjs" | "cjs" => "JavaScript",
+        "go" => "Go",
+        "rs" => "Rust",
+        "c" => "C",
+        "h" | "hpp" | "hh" | "hxx" | "cpp" | "cc" | "cxx" => "C++",
+        _ => return None,
+    };
+    Some(lang)
+}
+
+/// Returns the package of a `package` statement in Java and Kotlin files.
+pub fn declared_package(lang: &str, content: &str) -> Option<String> {
+    match lang {
+        "Java" | "Kotlin" => JAVA_PACKAGE
+            .captures(content)
+            .map(|capts| capts["package"].to_string()),
+        _ => None,
+    }
+}
+
+/// Parses import, use or include statements of a file.
+pub fn parse_imports(file: &str, lang: &str, content: &str) -> FileImports {
+    parse_imports_in(&CrateRoots::default(), file, lang, content)
+}
+
+/// Parses imports like `parse_imports`, module paths of Rust files are relative to their crate
+/// roots in `crate_roots`.
+pub fn parse_imports_in(
+    crate_roots: &CrateRoots,
+    file: &str,
+    lang: &str,
+    content: &str,
+) -> FileImports {
+    let mut imports = FileImports {
+        file: file.to_string(),
+        lang: lang.to_string(),
+        package: directory_of(file),
+        imports: vec![],
+    };
+
+    match lang {
+        "Java" | "Kotlin" => {
+            if let Some(package) = declared_package(lang, content) {
+                imports.package = package;
+            }
+            for capts in JAVA_IMPORT.captures_iter(content) {
+                let line_no = line_no_of(content, capts.get(0).unwrap().start());
+                imports
+                    .imports
+                    .push(ImportInfo::new(&capts["source"], line_no));
+            }
+        }
+        "TypeScript" | "JavaScript" => {
+            for capts in TS_IMPORT.captures_iter(content) {
+                let line_no = line_no_of(content, capts.get(0).unwrap().start());
+                let mut import = ImportInfo::new(&capts["source"], line_no);
+                import.names = type_script_names(&capts["names"]);
+                imports.imports.push(import);
+            }
+            for regex in [&*TS_SIDE_EFFECT_IMPORT, &*TS_REQUIRE].iter() {
+                for capts in regex.captures_iter(content) {
+                    let line_no = line_no_of(content, capts.get(0).unwrap().start());
+                    imports
+                        .imports
+                        .push(ImportInfo::new(&capts["source"], line_no));
+                }
+            }
+        }
+        "Go" => {
+            for capts in GO_IMPORT.captures_iter(content) {
+                let line_no = line_no_of(content, capts.get(0).unwrap().start());
+                imports
+                    .imports
+                    .push(ImportInfo::new(&capts["source"], line_no));
+            }
+            for block in GO_IMPORT_BLOCK.captures_iter(content) {
+                let block_match = block.name("block").unwrap();
+                for capts in GO_IMPORT_SPEC.captures_iter(block_match.as_str()) {
+                    let offset = block_match.start() + capts.get(0).unwrap().start();
+                    let line_no = line_no_of(content, offset);
+                    imports
+                        .imports
+                        .push(ImportInfo::new(&capts["source"], line_no));
+                }
+            }
+        }
+        "Rust" => {
+            imports.package = crate_roots.module_path(file);
+            // uses in inline modules, like `use super::*` in `mod tests`, import into the module
+            let modules = inline_modules(content);
+            for capts in RUST_USE.captures_iter(content) {
+                let tree = capts.name("tree").unwrap();
+                if modules.iter().any(|module| module.contains(&tree.start())) {
+                    continue;
+                }
+                let line_no = line_no_of(content, tree.start());
+                for path in expand_use_tree(tree.as_str()) {
+                    let (source, name) = match path.rsplit_once("::") {
+                        Some((source, name)) => (source.to_string(), name.to_string()),
+                        None => (path.clone(), "".to_string()),
+                    };
+                    let mut import = ImportInfo::new(&source, line_no);
+                    if !name.is_empty() && name != "*" && name != "self" {
+                        import.names.push(name);
+                    }
+                    imports.imports.push(import);
+                }
+            }
+        }
+        "C" | "C++" => {
+            for capts in C_INCLUDE.captures_iter(content) {
+                let line_no = line_no_of(content, capts.get(0).unwrap().start());
+                let mut import = ImportInfo::new(&capts["source"], line_no);
+                import.system = &capts["open"] == "<";
+                imports.imports.push(import);
+            }
+        }
+        _ => {}
+    }
+
+    imports.imports.sort_by_key(|import| import.line_no);
+    imports
+}
+
+/// Returns the ranges of inline `mod name { ... }` blocks in Rust code, braces in strings and
+/// line comments are skipped.
+fn inline_modules(content: &str) -> Vec<Range<usize>> {
+    let mut modules: Vec<Range<usize>> = vec![];
+    for found in RUST_INLINE_MOD.find_iter(content) {
+        if modules.iter().any(|module| module.contains(&found.start())) {
+            continue;
+        }
+
+        let mut depth = 0;
+        let mut chars = content[found.end() - 1..].char_indices();
+        let mut end = content.len();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = found.end() + index;
+                        break;
+                    }
+                }
+                '"' => {
+                    while let Some((_, c)) = chars.next() {
+                        match c {
+                            '\\' => {
+                                chars.next();
+                            }
+                            '"' => break,
+                            _ => {}
+                        }
+                    }
+                }
+                '/' if content[found.end() - 1 + index..].starts_with("//") => {
+                    for (_, c) in chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        modules.push(found.start()..end);
+    }
+    modules
+}
+
+/// Expands a Rust use tree, like `crate::render::{MermaidRender, graphviz_render::*}`
+fn expand_use_tree(tree: &str) -> Vec<String> {
+    let mut tree = tree.split_whitespace().collect::<Vec<&str>>().join(" ");
+    for (from, to) in [
+        ("{ ", "{"),
+        (" {", "{"),
+        (" }", "}"),
+        (", ", ","),
+        (" ,", ","),
+    ]
+    .iter()
+    {
+        tree = tree.replace(from, to);
+    }
+
+    let open = match tree.find('{') {
+        Some(open) => open,
+        None => {
+            let path = match tree.split_once(" as ") {
+                Some((path, _)) => path,
+                None => &tree,
+            };
+            return vec![path.to_string()];
+        }
+    };
+
+    let prefix = &tree[..open];
+    let inner = tree[open + 1..].trim_end_matches('}');
+    let mut paths = vec![];
+    for item in split_top_level(inner) {
+        if item.is_empty() {
+            continue;
+        }
+
+        for path in expand_use_tree(&item) {
+            if path == "self" {
+                paths.push(prefix.trim_end_matches("::").to_string());
+            } else {
+                paths.push(format!("{}{}", prefix, path));
+            }
+        }
+    }
+    paths
+}
+
+fn split_top_level(text: &str) -> Vec<String> {
+    let mut items = vec![];
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in text.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(current.clone());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    items.push(current);
+    items
+}
+
+fn type_script_names(names: &str) -> Vec<String> {
+    names
+        .split([',', '{', '}'])
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty() && !name.starts_with('*'))
+        .map(|name| match name.split_once(" as ") {
+            Some((_, alias)) => alias.trim().to_string(),
+            None => name.trim_start_matches("type ").to_string(),
+        })
+        .collect()
+}
+
+pub(crate) fn directory_of(file: &str) -> String {
+    match Path::new(file).parent() {
+        Some(parent) => {
+            let dir = format!("{}", parent.display());
+            dir.trim_start_matches("./").to_string()
+        }
+        None => "".to_string(),
+    }
+}
+
+fn line_no_of(content: &str, offset: usize) -> i32 {
+    content[..offset].matches('\n').count() as i32 + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::source::imports::{expand_use_tree, lang_by_extension, parse_imports};
+
+    #[test]
+    fn should_parse_java_imports() {
+        let code = "package com.phodal.order;
+
+import com.phodal.user.User;
+import static com.phodal.util.Strings.isEmpty;
+import java.util.*;
+
+public class Order {}";
+
+        let imports = parse_imports("src/Order.java", "Java", code);
+        assert_eq!("com.phodal.order", imports.package);
+        assert_eq!(3, imports.imports.len());
+        assert_eq!("com.phodal.user.User", imports.imports[0].source);
+        assert_eq!(3, imports.imports[0].line_no);
+        assert_eq!("com.phodal.util.Strings.isEmpty", imports.imports[1].source);
+        assert_eq!("java.util.*", imports.imports[2].source);
+    }
+
+    #[test]
+    fn should_parse_type_script_imports() {
+        let code = "import Animal from './animal';
+import {
+  Horse,
+  Snake as Python,
+} from \"../zoo/snake\";
+import './polyfill';
+const fs = require('fs');";
+
+        let imports = parse_imports("src/app/main.ts", "TypeScript", code);
+        assert_eq!("src/app", imports.package);
+        assert_eq!(4, imports.imports.len());
+        assert_eq!("./animal", imports.imports[0].source);
+        assert_eq!(vec!["Animal"], imports.imports[0].names);
+        assert_eq!("../zoo/snake", imports.imports[1].source);
+        assert_eq!(vec!["Horse", "Python"], imports.imports[1].names);
+        assert_eq!(2, imports.imports[1].line_no);
+        assert_eq!("./polyfill", imports.imports[2].source);
+        assert_eq!("fs", imports.imports[3].source);
+    }
+
+    #[test]
+    fn should_parse_go_imports() {
+        let code = "package store
+
+import \"fmt\"
+import (
+\t\"strings\"
+\tlog \"github.com/inherd/modeling/pkg/log\"
+)";
+
+        let imports = parse_imports("pkg/store/store.go", "Go", code);
+        assert_eq!("pkg/store", imports.package);
+        let sources: Vec<String> = imports.imports.iter().map(|i| i.source.clone()).collect();
+        assert_eq!(
+            vec!["fmt", "strings", "github.com/inherd/modeling/pkg/log"],
+            sources
+        );
+        assert_eq!(6, imports.imports[2].line_no);
+    }
+
+    #[test]
+    fn should_parse_rust_use_trees() {
+        let code = "use std::fs;
+use crate::render::{
+    MermaidRender, graphviz_render::GraphvizRender,
+};
+pub use crate::coco_struct::ClassInfo as Info;";
+
+        let imports = parse_imports("src/lib.rs", "Rust", code);
+        assert_eq!("crate", imports.package);
+        let sources: Vec<String> = imports.imports.iter().map(|i| i.source.clone()).collect();
+        assert_eq!(
+            vec![
+                "std",
+                "crate::render",
+                "crate::render::graphviz_render",
+                "crate::coco_struct"
+            ],
+            sources
+        );
+        assert_eq!(vec!["ClassInfo"], imports.imports[3].names);
+        assert_eq!(5, imports.imports[3].line_no);
+    }
+
+    #[test]
+    fn should_skip_uses_in_inline_modules() {
+        let code = "use crate::coco_struct::ClassInfo;
+
+mod geometry {
+    use std::f64::consts::PI;
+    const NAME: &str = \"}\"; // }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    mod nested {
+        use crate::render::MermaidRender;
+    }
+    use crate::testing::class;
+}
+
+use crate::ParseOption;";
+
+        let imports = parse_imports("src/lib.rs", "Rust", code);
+        let sources: Vec<String> = imports.imports.iter().map(|i| i.source.clone()).collect();
+        assert_eq!(vec!["crate::coco_struct", "crate"], sources);
+    }
+
+    #[test]
+    fn should_expand_self_in_use_tree() {
+        assert_eq!(
+            vec!["crate::render", "crate::render::PlantUmlRender"],
+            expand_use_tree("crate::render::{self, PlantUmlRender}")
+        );
+    }
+
+    #[test]
+    fn should_parse_c_includes() {
+        let code = "#include <iostream>\n#include \"orm/field.h\"";
+
+        let imports = parse_imports("src/main.cpp", "C++", code);
+        assert_eq!(2, imports.imports.len());
+        assert!(imports.imports[0].system);
+        assert_eq!("orm/field.h", imports.imports[1].source);
+        assert!(!imports.imports[1].system);
+    }
+
+    #[test]
+    fn should_detect_lang_by_extension() {
+        assert_eq!(Some("Rust"), lang_by_extension("src/lib.rs"));
+        assert_eq!(Some("C++"), lang_by_extension("include/field.h"));
+        assert_eq!(None, lang_by_extension("README.md"));
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::ctags::rust_module::CrateRoots;
+use crate::source::annotation::annotations_of;
+use crate::source::imports::{declared_package, lang_by_extension, parse_imports_in, FileImports};
+use crate::ClassInfo;
+
+pub mod annotation;
+pub mod dependency;
+pub mod imports;
+pub mod resolve;
+
+/// Reads the source file of classes one file at a time, so only one file is held in memory.
+/// Annotations of classes are filled, packages of Java and Kotlin classes are prefixed with the
+/// `package` statement of their files, and imports of the files are returned for
+/// `resolve::resolve_types`.
+///
+/// File names are resolved against `root`, which is the directory ctags was called in, Rust files
+/// are in crates of `crate_roots`.
+pub fn read_sources<P: AsRef<Path>>(
+    classes: &mut [ClassInfo],
+    root: P,
+    crate_roots: &CrateRoots,
+) -> Vec<FileImports> {
+    let mut by_file: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (index, clazz) in classes.iter().enumerate() {
+        by_file.entry(clazz.file.clone()).or_default().push(index);
+    }
+
+    let mut files = vec![];
+    for (file, indexes) in by_file {
+        let content = match fs::read_to_string(root.as_ref().join(&file)) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+
+        let lines: Vec<&str> = content.lines().collect();
+        let lang = lang_by_extension(&file);
+        let package = lang.and_then(|lang| declared_package(lang, &content));
+        for index in indexes {
+            let clazz = &mut classes[index];
+            // the parser only knows the enclosing classes of nested classes, like `Order.Item`
+            if let Some(package) = &package {
+                clazz.package = if clazz.package.is_empty() {
+                    package.clone()
+                } else {
+                    format!("{}.{}", package, clazz.package)
+                };
+            }
+            if clazz.line_no > 0 {
+                clazz.annotations =
+                    annotations_of(&clazz.lang, &clazz.name, &lines, clazz.line_no as usize);
+            }
+        }
+
+        if let Some(lang) = lang {
+            files.push(parse_imports_in(crate_roots, &file, lang, &content));
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use crate::ctags::rust_module::CrateRoots;
+    use crate::source::read_sources;
+    use crate::testing::class;
+
+    #[test]
+    fn should_read_annotations_and_imports_of_files() {
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path().join("Order.java"),
+            "package shop;\nimport shop.user.User;\n@Entity\nclass Order {}\nclass Item {}",
+        )
+        .unwrap();
+        let mut order = class("Java", "", "Order").in_file("Order.java");
+        order.line_no = 4;
+        let mut item = class("Java", "", "Item").in_file("Order.java");
+        item.line_no = 5;
+        let mut classes = vec![order, item];
+
+        let files = read_sources(&mut classes, temp.path(), &CrateRoots::default());
+
+        assert_eq!(vec!["Entity"], classes[0].annotations);
+        assert_eq!("shop", classes[0].package);
+        assert!(classes[1].annotations.is_empty());
+        assert_eq!(1, files.len());
+        assert_eq!("shop", files[0].package);
+        assert_eq!("shop.user.User", files[0].imports[0].source);
+    }
+}
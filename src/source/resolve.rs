@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+
+use crate::source::dependency::ImportResolver;
+use crate::source::imports::{FileImports, ImportInfo};
+use crate::ClassInfo;
+
+/// Resolves member types and return types of classes to the qualified name of a class in the
+/// model, by the imports and the package of each file. Types which can't be resolved, like
+/// `String` or an imported library class, are marked as `external`.
+///
+/// `files` are imports of the files of classes, read by `source::read_sources`.
+pub fn resolve_types(classes: &mut [ClassInfo], files: &[FileImports]) {
+    let resolved = {
+        let resolver = TypeResolver::new(classes, files);
+        classes
+            .iter()
+            .map(|clazz| {
+                let members: Vec<Option<String>> = clazz
+                    .members
+                    .iter()
+                    .map(|member| {
+                        resolver.resolve(clazz, &member.pure_data_type, &member.data_type)
+                    })
+                    .collect();
+                let methods: Vec<Option<String>> = clazz
+                    .methods
+                    .iter()
+                    .map(|method| {
+                        resolver.resolve(clazz, &method.pure_return_type, &method.return_type)
+                    })
+                    .collect();
+                (members, methods)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    for (clazz, (members, methods)) in classes.iter_mut().zip(resolved) {
+        for (member, qualified) in clazz.members.iter_mut().zip(members) {
+            if !member.data_type.is_empty() {
+                member.external = qualified.is_none();
+                member.qualified_type = qualified.unwrap_or_default();
+            }
+        }
+        for (method, qualified) in clazz.methods.iter_mut().zip(methods) {
+            if !method.return_type.is_empty() {
+                method.external = qualified.is_none();
+                method.qualified_return_type = qualified.unwrap_or_default();
+            }
+        }
+    }
+}
+
+struct ClassKey {
+    qualified: String,
+    package: String,
+    file: String,
+    lang: String,
+}
+
+/// languages which must import classes of other packages before using them
+const IMPORT_REQUIRED: [&str; 6] = ["Java", "Kotlin", "TypeScript", "JavaScript", "Go", "Rust"];
+
+struct TypeResolver<'a> {
+    classes: Vec<ClassKey>,
+    by_name: HashMap<String, Vec<usize>>,
+    files: HashMap<&'a str, &'a FileImports>,
+    resolver: ImportResolver<'a>,
+}
+
+impl<'a> TypeResolver<'a> {
+    fn new(classes: &[ClassInfo], files: &'a [FileImports]) -> TypeResolver<'a> {
+        let mut by_name: HashMap<String, Vec<usize>> = HashMap::default();
+        let classes: Vec<ClassKey> = classes
+            .iter()
+            .enumerate()
+            .map(|(index, clazz)| {
+                by_name.entry(clazz.name.clone()).or_default().push(index);
+                ClassKey {
+                    qualified: clazz.qualified_name(),
+                    package: clazz.package.clone(),
+                    file: clazz.file.clone(),
+                    lang: clazz.lang.clone(),
+                }
+            })
+            .collect();
+
+        TypeResolver {
+            classes,
+            by_name,
+            files: files
+                .iter()
+                .map(|imports| (imports.file.as_str(), imports))
+                .collect(),
+            resolver: ImportResolver::new(files),
+        }
+    }
+
+    fn resolve(&self, clazz: &ClassInfo, pure_type: &str, data_type: &str) -> Option<String> {
+        let type_name = if pure_type.is_empty() {
+            base_type(data_type)
+        } else {
+            base_type(pure_type)
+        };
+        if type_name.is_empty() {
+            return None;
+        }
+
+        let (qualifier, simple) = match type_name.rfind(['.', ':']) {
+            Some(index) => (
+                type_name[..index].trim_end_matches(':'),
+                &type_name[index + 1..],
+            ),
+            None => ("", type_name),
+        };
+        let candidates: Vec<&ClassKey> = self
+            .by_name
+            .get(simple)?
+            .iter()
+            .map(|index| &self.classes[*index])
+            .filter(|candidate| candidate.lang == clazz.lang)
+            .collect();
+
+        if let Some(found) = candidates.iter().find(|c| c.qualified == type_name) {
+            return Some(found.qualified.clone());
+        }
+
+        if qualifier.is_empty() {
+            if let Some(found) = candidates.iter().find(|c| c.file == clazz.file) {
+                return Some(found.qualified.clone());
+            }
+        }
+
+        let mut imported = false;
+        if let Some(file) = self.files.get(clazz.file.as_str()) {
+            for import in &file.imports {
+                if !is_imported(&clazz.lang, import, qualifier, simple) {
+                    continue;
+                }
+
+                imported = true;
+                let targets = self.resolver.resolve(file, import);
+                if let Some(found) = candidates
+                    .iter()
+                    .find(|c| targets.contains(&c.file.as_str()))
+                {
+                    return Some(found.qualified.clone());
+                }
+            }
+        }
+
+        if qualifier.is_empty() {
+            if let Some(found) = candidates.iter().find(|c| c.package == clazz.package) {
+                return Some(found.qualified.clone());
+            }
+        }
+
+        if imported || IMPORT_REQUIRED.contains(&clazz.lang.as_str()) {
+            return None;
+        }
+
+        // without imports, only a class with a unique name can be told apart
+        match candidates.len() {
+            1 => Some(candidates[0].qualified.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Returns whether `import` may bring `simple`, used as `qualifier.simple` or `simple`, into scope.
+fn is_imported(lang: &str, import: &ImportInfo, qualifier: &str, simple: &str) -> bool {
+    match lang {
+        "Java" | "Kotlin" => {
+            import.source.ends_with(&format!(".{}", simple)) || import.source.ends_with(".*")
+        }
+        "Go" => {
+            !qualifier.is_empty()
+                && (import.source == qualifier
+                    || import.source.ends_with(&format!("/{}", qualifier)))
+        }
+        "Rust" => {
+            // glob imports, or a module imported to be used as `module::Type`
+            let module = qualifier.rsplit("::").next().unwrap_or("");
+            import
+                .names
+                .iter()
+                .any(|name| name == simple || name == module)
+                || import.names.is_empty()
+        }
+        "TypeScript" | "JavaScript" => import.names.iter().any(|name| name == simple),
+        _ => true,
+    }
+}
+
+const TYPE_PREFIXES: [&str; 7] = ["&", "mut ", "dyn ", "impl ", "*", "[]", "..."];
+
+/// Returns the type without references, pointers, arrays and generic arguments,
+/// like `Order` for `&mut Order`, `*Order` or `[]Order`.
+fn base_type(data_type: &str) -> &str {
+    let mut data_type = data_type.trim();
+    while let Some(prefix) = TYPE_PREFIXES.iter().find(|p| data_type.starts_with(*p)) {
+        data_type = data_type[prefix.len()..].trim_start();
+    }
+
+    let end = data_type
+        .find(|c: char| c == '<' || c == '[' || c == '|' || c.is_whitespace())
+        .unwrap_or(data_type.len());
+    &data_type[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::source::imports::parse_imports;
+    use crate::source::resolve::{base_type, TypeResolver};
+    use crate::testing::class;
+
+    #[test]
+    fn should_get_base_type() {
+        assert_eq!("Order", base_type("&mut Order"));
+        assert_eq!("store.Item", base_type("*store.Item"));
+        assert_eq!("Order", base_type("[]Order"));
+        assert_eq!("Order", base_type("Order[]"));
+        assert_eq!("List", base_type("List<Order>"));
+    }
+
+    #[test]
+    fn should_resolve_java_types_by_imports() {
+        let classes = vec![
+            class("Java", "com.shop.billing", "Invoice").in_file("billing/Invoice.java"),
+            class("Java", "com.a", "Order").in_file("a/Order.java"),
+            class("Java", "com.b", "Order").in_file("b/Order.java"),
+            class("Java", "com.shop.billing", "List").in_file("billing/List.java"),
+        ];
+        let files = vec![
+            parse_imports(
+                "billing/Invoice.java",
+                "Java",
+                "package com.shop.billing;\nimport com.b.Order;\nimport java.util.*;",
+            ),
+            parse_imports("a/Order.java", "Java", "package com.a;"),
+            parse_imports("b/Order.java", "Java", "package com.b;"),
+            parse_imports("billing/List.java", "Java", "package com.shop.billing;"),
+        ];
+
+        let resolver = TypeResolver::new(&classes, &files);
+        let invoice = &classes[0];
+        assert_eq!(
+            Some("com.b.Order".to_string()),
+            resolver.resolve(invoice, "", "Order")
+        );
+        assert_eq!(
+            Some("com.a.Order".to_string()),
+            resolver.resolve(invoice, "", "com.a.Order")
+        );
+        assert_eq!(
+            Some("com.shop.billing.List".to_string()),
+            resolver.resolve(invoice, "", "List")
+        );
+        assert_eq!(None, resolver.resolve(invoice, "", "String"));
+    }
+
+    #[test]
+    fn should_flag_type_imported_from_outside_as_external() {
+        let classes = vec![
+            class("TypeScript", "src/app", "Main").in_file("src/app/main.ts"),
+            class("TypeScript", "src/log", "Logger").in_file("src/log/logger.ts"),
+        ];
+        let files = vec![parse_imports(
+            "src/app/main.ts",
+            "TypeScript",
+            "import { Logger } from 'winston';",
+        )];
+
+        let resolver = TypeResolver::new(&classes, &files);
+        assert_eq!(None, resolver.resolve(&classes[0], "", "Logger"));
+    }
+
+    #[test]
+    fn should_resolve_go_qualified_types() {
+        let classes = vec![
+            class("Go", "cmd", "Server").in_file("cmd/main.go"),
+            class("Go", "pkg/store", "Item").in_file("pkg/store/item.go"),
+            class("Go", "pkg/cache", "Item").in_file("pkg/cache/item.go"),
+        ];
+        let files = vec![
+            parse_imports(
+                "cmd/main.go",
+                "Go",
+                "package main\nimport \"github.com/inherd/app/pkg/store\"",
+            ),
+            parse_imports("pkg/store/item.go", "Go", "package store"),
+            parse_imports("pkg/cache/item.go", "Go", "package cache"),
+        ];
+
+        let resolver = TypeResolver::new(&classes, &files);
+        assert_eq!(
+            Some("pkg/store.Item".to_string()),
+            resolver.resolve(&classes[0], "", "*store.Item")
+        );
+        assert_eq!(None, resolver.resolve(&classes[0], "", "Item"));
+    }
+
+    #[test]
+    fn should_resolve_rust_use_and_module_types() {
+        let classes = vec![
+            class("Rust", "crate::ctags::ctags_parser", "CtagsParser")
+                .in_file("src/ctags/ctags_parser.rs"),
+            class("Rust", "crate::coco_struct", "ClassInfo").in_file("src/coco_struct.rs"),
+            class("Rust", "crate::parse_option", "ParseOption").in_file("src/parse_option.rs"),
+        ];
+        let files = vec![
+            parse_imports(
+                "src/ctags/ctags_parser.rs",
+                "Rust",
+                "use crate::coco_struct::ClassInfo;",
+            ),
+            parse_imports("src/coco_struct.rs", "Rust", ""),
+            parse_imports("src/parse_option.rs", "Rust", ""),
+        ];
+
+        let resolver = TypeResolver::new(&classes, &files);
+        assert_eq!(
+            Some("crate::coco_struct::ClassInfo".to_string()),
+            resolver.resolve(&classes[0], "ClassInfo", "Vec<ClassInfo>")
+        );
+        assert_eq!(None, resolver.resolve(&classes[0], "", "ParseOption"));
+        assert_eq!(
+            Some("crate::parse_option::ParseOption".to_string()),
+            resolver.resolve(&classes[0], "", "crate::parse_option::ParseOption")
+        );
+    }
+
+    #[test]
+    fn should_fallback_to_unique_name_without_imports() {
+        let classes = vec![
+            class("Python", "", "Shape").in_file("shape.py"),
+            class("Python", "", "Circle").in_file("geometry/circle.py"),
+        ];
+
+        let resolver = TypeResolver::new(&classes, &[]);
+        assert_eq!(
+            Some("Circle".to_string()),
+            resolver.resolve(&classes[0], "", "Circle")
+        );
+    }
+}
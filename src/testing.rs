@@ -0,0 +1,48 @@
+//! Fixtures shared by tests, the module is only compiled with `cfg(test)`.
+
+use crate::coco_struct::{ClassInfo, MemberInfo, MethodInfo};
+
+/// Returns a class for tests, like `class("Java", "shop", "Order").in_file("shop/Order.java")`
+pub fn class(lang: &str, package: &str, name: &str) -> ClassInfo {
+    let mut clazz = ClassInfo::new(name);
+    clazz.lang = lang.to_string();
+    clazz.package = package.to_string();
+    clazz
+}
+
+/// builders of fixtures, not a part of the API
+#[cfg(test)]
+impl ClassInfo {
+    pub fn in_file(mut self, file: &str) -> ClassInfo {
+        self.file = file.to_string();
+        self
+    }
+
+    pub fn of_kind(mut self, kind: &str) -> ClassInfo {
+        self.kind = kind.to_string();
+        self
+    }
+
+    pub fn in_module(mut self, module: &str) -> ClassInfo {
+        self.module = module.to_string();
+        self
+    }
+
+    /// Adds `String` members of `names`
+    pub fn with_members(mut self, names: &[&str]) -> ClassInfo {
+        for name in names {
+            let member = MemberInfo::new(name, "-".to_string(), "String".to_string());
+            self.members.push(member);
+        }
+        self
+    }
+
+    /// Adds `void` methods of `names` without parameters
+    pub fn with_methods(mut self, names: &[&str]) -> ClassInfo {
+        for name in names {
+            let method = MethodInfo::new(name, "+".to_string(), vec![], "void".to_string());
+            self.methods.push(method);
+        }
+        self
+    }
+}
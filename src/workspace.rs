@@ -0,0 +1,601 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::source::dependency::DependencyEdge;
+use crate::ClassInfo;
+
+lazy_static! {
+    static ref XML_COMMENT: Regex = Regex::new(r"(?s)<!--.*?-->").unwrap();
+    static ref POM_PARENT: Regex = Regex::new(r"(?s)<parent>.*?</parent>").unwrap();
+    static ref POM_ARTIFACT_ID: Regex =
+        Regex::new(r"<artifactId>\s*([^<\s]+)\s*</artifactId>").unwrap();
+    static ref POM_MODULE: Regex = Regex::new(r"<module>\s*([^<]+?)\s*</module>").unwrap();
+    static ref GRADLE_INCLUDE: Regex =
+        Regex::new(r#"(?m)^\s*include\s*\(?((?:\s*['"][^'"]+['"]\s*,?)+)"#).unwrap();
+    static ref QUOTED: Regex = Regex::new(r#"['"]([^'"]+)['"]"#).unwrap();
+    static ref GO_MODULE: Regex = Regex::new(r"(?m)^\s*module\s+(\S+)").unwrap();
+}
+
+/// dirs never searched for modules
+const SKIP_DIRS: [&str; 4] = ["node_modules", "target", "build", "vendor"];
+
+/// A module declared by the build files of a workspace.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Module {
+    /// declared name, like the `artifactId` of Maven or the package name of Cargo and npm
+    pub name: String,
+    pub path: PathBuf,
+    /// build tool which declares the module: maven, gradle, cargo, go or npm
+    pub build: String,
+}
+
+impl Module {
+    pub fn new<P: AsRef<Path>>(name: &str, path: P, build: &str) -> Module {
+        Module {
+            name: name.to_string(),
+            path: path.as_ref().to_path_buf(),
+            build: build.to_string(),
+        }
+    }
+
+    /// Returns the name usable in file names, like `core` for `@app/core` of npm or
+    /// `github.com/app/core` of Go.
+    pub fn output_name(&self) -> String {
+        match self.name.rsplit('/').next() {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => self.name.replace('/', "-"),
+        }
+    }
+}
+
+/// Detects modules in `root` from Maven `pom.xml`, Gradle `settings.gradle`, Cargo workspaces,
+/// Go `go.mod` and npm workspaces, nested modules are returned instead of their parent.
+/// Modules with the same name are renamed by their relative path, like `order-core`.
+/// Returns an empty vec if no workspace is declared.
+pub fn detect_modules<P: AsRef<Path>>(root: P) -> Vec<Module> {
+    let root = root.as_ref();
+    let mut modules = vec![];
+    modules.extend(maven_modules(root));
+    modules.extend(gradle_modules(root));
+    modules.extend(cargo_modules(root));
+    modules.extend(go_modules(root));
+    modules.extend(npm_modules(root));
+
+    let mut paths = HashSet::new();
+    modules.retain(|module| paths.insert(module.path.clone()));
+    unique_names(root, &mut modules);
+    modules
+}
+
+fn maven_modules(dir: &Path) -> Vec<Module> {
+    let pom = match fs::read_to_string(dir.join("pom.xml")) {
+        Ok(pom) => XML_COMMENT.replace_all(&pom, "").to_string(),
+        Err(_) => return vec![],
+    };
+
+    let mut modules = vec![];
+    for capture in POM_MODULE.captures_iter(&pom) {
+        let path = dir.join(&capture[1]);
+        let children = maven_modules(&path);
+        if !children.is_empty() {
+            modules.extend(children);
+            continue;
+        }
+
+        if let Ok(child) = fs::read_to_string(path.join("pom.xml")) {
+            let child = POM_PARENT
+                .replace(&XML_COMMENT.replace_all(&child, ""), "")
+                .to_string();
+            if let Some(artifact) = POM_ARTIFACT_ID.captures(&child) {
+                modules.push(Module::new(&artifact[1], &path, "maven"));
+            }
+        }
+    }
+
+    modules
+}
+
+fn gradle_modules(root: &Path) -> Vec<Module> {
+    let settings = ["settings.gradle", "settings.gradle.kts"]
+        .iter()
+        .find_map(|name| fs::read_to_string(root.join(name)).ok());
+    let settings = match settings {
+        Some(settings) => settings,
+        None => return vec![],
+    };
+
+    let mut projects = vec![];
+    for include in GRADLE_INCLUDE.captures_iter(&settings) {
+        for project in QUOTED.captures_iter(&include[1]) {
+            let project = project[1].trim_start_matches(':').to_string();
+            if !project.is_empty() {
+                projects.push(project);
+            }
+        }
+    }
+
+    // `:app` is the parent of `:app:core`
+    let parents: HashSet<String> = projects
+        .iter()
+        .filter_map(|project| {
+            project
+                .rsplit_once(':')
+                .map(|(parent, _)| parent.to_string())
+        })
+        .collect();
+
+    projects
+        .iter()
+        .filter(|project| !parents.contains(*project))
+        .map(|project| {
+            let name = project.rsplit(':').next().unwrap_or(project);
+            Module::new(name, root.join(project.replace(':', "/")), "gradle")
+        })
+        .filter(|module| module.path.is_dir())
+        .collect()
+}
+
+fn cargo_modules(root: &Path) -> Vec<Module> {
+    let manifest = match read_toml(&root.join("Cargo.toml")) {
+        Some(manifest) => manifest,
+        None => return vec![],
+    };
+    let workspace = match manifest.get("workspace") {
+        Some(workspace) => workspace,
+        None => return vec![],
+    };
+
+    let members = toml_strings(workspace.get("members"));
+    let excludes = toml_strings(workspace.get("exclude"));
+    find_members(root, "Cargo.toml", &members, &excludes)
+        .into_iter()
+        .filter_map(|path| {
+            let manifest = read_toml(&path.join("Cargo.toml"))?;
+            let name = manifest.get("package")?.get("name")?.as_str()?.to_string();
+            Some(Module::new(&name, path, "cargo"))
+        })
+        .collect()
+}
+
+fn go_modules(root: &Path) -> Vec<Module> {
+    let mut modules = vec![];
+    for path in find_dirs_with(root, "go.mod") {
+        if path == root {
+            continue;
+        }
+
+        if let Ok(go_mod) = fs::read_to_string(path.join("go.mod")) {
+            if let Some(module) = GO_MODULE.captures(&go_mod) {
+                modules.push(Module::new(&module[1], &path, "go"));
+            }
+        }
+    }
+    modules
+}
+
+fn npm_modules(root: &Path) -> Vec<Module> {
+    let package = match read_json(&root.join("package.json")) {
+        Some(package) => package,
+        None => return vec![],
+    };
+
+    // `"workspaces": ["packages/*"]` or `"workspaces": { "packages": ["packages/*"] }`
+    let workspaces = match package.get("workspaces") {
+        Some(serde_json::Value::Object(workspaces)) => workspaces.get("packages"),
+        workspaces => workspaces,
+    };
+    let members: Vec<String> = workspaces
+        .and_then(|workspaces| workspaces.as_array())
+        .map(|patterns| {
+            patterns
+                .iter()
+                .filter_map(|pattern| pattern.as_str().map(|pattern| pattern.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    find_members(root, "package.json", &members, &[])
+        .into_iter()
+        .filter_map(|path| {
+            let package = read_json(&path.join("package.json"))?;
+            let name = package.get("name")?.as_str()?.to_string();
+            Some(Module::new(&name, path, "npm"))
+        })
+        .collect()
+}
+
+/// Returns dirs with the `manifest` which match globs of `members` and don't match `excludes`.
+fn find_members(
+    root: &Path,
+    manifest: &str,
+    members: &[String],
+    excludes: &[String],
+) -> Vec<PathBuf> {
+    if members.is_empty() {
+        return vec![];
+    }
+
+    let members = match build_globs(members) {
+        Some(globs) => globs,
+        None => return vec![],
+    };
+    let excludes = build_globs(excludes).unwrap_or_else(GlobSet::empty);
+
+    find_dirs_with(root, manifest)
+        .into_iter()
+        .filter(|path| {
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            members.is_match(relative) && !excludes.is_match(relative)
+        })
+        .collect()
+}
+
+fn build_globs(patterns: &[String]) -> Option<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        match GlobBuilder::new(pattern).literal_separator(true).build() {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(err) => eprintln!("error: {:?}", err),
+        }
+    }
+    builder.build().ok()
+}
+
+/// Returns dirs under `root` which contain the file, in the order of walking.
+fn find_dirs_with(root: &Path, file_name: &str) -> Vec<PathBuf> {
+    WalkBuilder::new(root)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            !SKIP_DIRS.contains(&name.as_ref())
+        })
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_name() == file_name)
+        .filter_map(|entry| entry.path().parent().map(|dir| dir.to_path_buf()))
+        .collect()
+}
+
+fn read_toml(path: &Path) -> Option<toml::Value> {
+    toml::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn read_json(path: &Path) -> Option<serde_json::Value> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn toml_strings(value: Option<&toml::Value>) -> Vec<String> {
+    value
+        .and_then(|value| value.as_array())
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|value| value.as_str().map(|value| value.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn unique_names<P: AsRef<Path>>(root: P, modules: &mut [Module]) {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for module in modules.iter() {
+        *counts.entry(module.output_name()).or_default() += 1;
+    }
+
+    for module in modules.iter_mut() {
+        if counts[&module.output_name()] > 1 {
+            let relative = module
+                .path
+                .strip_prefix(root.as_ref())
+                .unwrap_or(&module.path);
+            module.name = relative.to_string_lossy().replace(['/', '\\'], "-");
+        }
+    }
+}
+
+/// Sets `module` of classes to the output name of the module whose path contains the file,
+/// the innermost one for nested modules.
+pub fn tag_modules(classes: &mut [ClassInfo], modules: &[Module]) {
+    for clazz in classes.iter_mut() {
+        if let Some(module) = module_of(Path::new(&clazz.file), modules) {
+            clazz.module = module.output_name();
+        }
+    }
+}
+
+/// Returns the innermost module of `file`, or `None` if it's out of all modules.
+pub fn module_of<'a>(file: &Path, modules: &'a [Module]) -> Option<&'a Module> {
+    modules
+        .iter()
+        .filter(|module| file.starts_with(&module.path))
+        .max_by_key(|module| module.path.components().count())
+}
+
+/// Dependencies between modules, a class depends on another module when its parents, fields or
+/// method return types are classes of that module, the weight is the count of these references.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ModuleGraph {
+    /// packages of each module
+    pub modules: BTreeMap<String, Vec<String>>,
+    pub edges: Vec<DependencyEdge>,
+}
+
+impl ModuleGraph {
+    /// Builds the graph from classes tagged by `tag_modules`, classes without module are ignored.
+    pub fn build(classes: &[ClassInfo]) -> ModuleGraph {
+        let mut modules: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut by_qualified: HashMap<String, &str> = HashMap::new();
+        let mut by_name: HashMap<&str, Vec<&str>> = HashMap::new();
+        for clazz in classes.iter().filter(|clazz| !clazz.module.is_empty()) {
+            let packages = modules.entry(clazz.module.clone()).or_default();
+            if !packages.contains(&clazz.package) {
+                packages.push(clazz.package.clone());
+            }
+            by_qualified.insert(clazz.qualified_name(), &clazz.module);
+            by_name.entry(&clazz.name).or_default().push(&clazz.module);
+        }
+        for packages in modules.values_mut() {
+            packages.sort();
+        }
+
+        let mut weights: BTreeMap<(String, String), usize> = BTreeMap::new();
+        for clazz in classes.iter().filter(|clazz| !clazz.module.is_empty()) {
+            // parents are not resolved, so they are found by the name if it's unique
+            let parents = clazz.parents.iter().filter_map(|parent| {
+                by_qualified.get(parent).copied().or_else(|| {
+                    match by_name
+                        .get(parent.as_str())
+                        .map(|modules| modules.as_slice())
+                    {
+                        Some([module]) => Some(*module),
+                        _ => None,
+                    }
+                })
+            });
+            let members = clazz
+                .members
+                .iter()
+                .filter(|member| !member.external)
+                .filter_map(|member| by_qualified.get(&member.qualified_type).copied());
+            let methods = clazz
+                .methods
+                .iter()
+                .filter(|method| !method.external)
+                .filter_map(|method| by_qualified.get(&method.qualified_return_type).copied());
+
+            for target in parents.chain(members).chain(methods) {
+                if target != clazz.module {
+                    let key = (clazz.module.clone(), target.to_string());
+                    *weights.entry(key).or_default() += 1;
+                }
+            }
+        }
+
+        let edges = weights
+            .into_iter()
+            .map(|((source, target), weight)| DependencyEdge {
+                source,
+                target,
+                weight,
+            })
+            .collect();
+        ModuleGraph { modules, edges }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use tempfile::TempDir;
+
+    use crate::testing::class;
+    use crate::workspace::{detect_modules, tag_modules, unique_names, Module, ModuleGraph};
+    use crate::{ClassInfo, MemberInfo, MethodInfo};
+
+    fn write(root: &Path, file: &str, content: &str) {
+        let path = root.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn names(modules: &[Module]) -> Vec<String> {
+        modules.iter().map(|module| module.output_name()).collect()
+    }
+
+    #[test]
+    fn should_detect_nested_maven_modules() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(root, "pom.xml", "<modules><module>app</module><module>shared</module><!-- <module>old</module> --></modules>");
+        write(root, "app/pom.xml", "<parent><artifactId>root</artifactId></parent><artifactId>app</artifactId><modules>\n<module>app-api</module>\n<module>app-core</module>\n</modules>");
+        write(
+            root,
+            "app/app-api/pom.xml",
+            "<parent><artifactId>app</artifactId></parent><artifactId>api</artifactId>",
+        );
+        write(
+            root,
+            "app/app-core/pom.xml",
+            "<artifactId>core</artifactId>",
+        );
+        write(
+            root,
+            "shared/pom.xml",
+            "<artifactId>shared-kernel</artifactId>",
+        );
+
+        let modules = detect_modules(root);
+
+        assert_eq!(vec!["api", "core", "shared-kernel"], names(&modules));
+        assert_eq!(root.join("app/app-api"), modules[0].path);
+        assert_eq!("maven", modules[0].build);
+    }
+
+    #[test]
+    fn should_detect_gradle_projects() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(root, "settings.gradle.kts", "rootProject.name = \"shop\"\ninclude(\":app\", \":app:core\",\n    \":web\")\ninclude ':missing'\n");
+        fs::create_dir_all(root.join("app/core")).unwrap();
+        fs::create_dir_all(root.join("web")).unwrap();
+
+        let modules = detect_modules(root);
+
+        assert_eq!(vec!["core", "web"], names(&modules));
+        assert_eq!(root.join("app/core"), modules[0].path);
+    }
+
+    #[test]
+    fn should_detect_cargo_workspace_members() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"cli\", \"crates/*\"]\nexclude = [\"crates/legacy\"]\n",
+        );
+        write(root, "cli/Cargo.toml", "[package]\nname = \"shop-cli\"\n");
+        write(
+            root,
+            "crates/model/Cargo.toml",
+            "[package]\nname = \"shop-model\"\n",
+        );
+        write(
+            root,
+            "crates/legacy/Cargo.toml",
+            "[package]\nname = \"legacy\"\n",
+        );
+        write(
+            root,
+            "crates/model/tests/fixture/Cargo.toml",
+            "[package]\nname = \"fixture\"\n",
+        );
+
+        let mut modules = names(&detect_modules(root));
+        modules.sort();
+
+        assert_eq!(vec!["shop-cli", "shop-model"], modules);
+    }
+
+    #[test]
+    fn should_detect_go_and_npm_modules() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(root, "go.mod", "module github.com/shop/root\n");
+        write(
+            root,
+            "services/order/go.mod",
+            "module github.com/shop/order\n\ngo 1.17\n",
+        );
+        write(
+            root,
+            "package.json",
+            r#"{"workspaces": {"packages": ["web/*"]}}"#,
+        );
+        write(root, "web/ui/package.json", r#"{"name": "@shop/ui"}"#);
+        write(
+            root,
+            "web/ui/node_modules/dep/package.json",
+            r#"{"name": "dep"}"#,
+        );
+
+        let modules = detect_modules(root);
+
+        assert_eq!(vec!["order", "ui"], names(&modules));
+        assert_eq!("github.com/shop/order", modules[0].name);
+        assert_eq!("@shop/ui", modules[1].name);
+    }
+
+    #[test]
+    fn should_rename_modules_with_same_name() {
+        let root = Path::new("/shop");
+        let mut modules = vec![
+            Module::new("core", root.join("order/core"), "gradle"),
+            Module::new("core", root.join("user/core"), "gradle"),
+            Module::new("web", root.join("web"), "gradle"),
+        ];
+
+        unique_names(root, &mut modules);
+
+        assert_eq!(vec!["order-core", "user-core", "web"], names(&modules));
+    }
+
+    #[test]
+    fn should_tag_classes_by_innermost_module() {
+        let modules = vec![
+            Module::new("app", "shop/app", "maven"),
+            Module::new("api", "shop/app/api", "maven"),
+        ];
+        let mut classes = vec![ClassInfo::new("Order"), ClassInfo::new("OrderApi")];
+        classes[0].file = "shop/app/src/Order.java".to_string();
+        classes[1].file = "shop/app/api/src/OrderApi.java".to_string();
+
+        tag_modules(&mut classes, &modules);
+
+        assert_eq!("app", classes[0].module);
+        assert_eq!("api", classes[1].module);
+    }
+
+    #[test]
+    fn should_build_weighted_module_dependencies() {
+        let mut controller = class("Java", "shop.web", "OrderController").in_module("web");
+        let mut field = MemberInfo::new("service", "-".to_string(), "OrderService".to_string());
+        field.qualified_type = "shop.order.OrderService".to_string();
+        controller.members.push(field);
+        let mut method = MethodInfo::new("find", "+".to_string(), vec![], "Order".to_string());
+        method.qualified_return_type = "shop.order.Order".to_string();
+        controller.methods.push(method);
+        let mut external = MemberInfo::new("name", "-".to_string(), "String".to_string());
+        external.external = true;
+        controller.members.push(external);
+
+        let mut service = class("Java", "shop.order", "OrderService").in_module("order");
+        service.parents.push("BaseService".to_string());
+        let mut order = class("Java", "shop.order", "Order").in_module("order");
+        let mut same_module = MemberInfo::new("items", "-".to_string(), "Order".to_string());
+        same_module.qualified_type = "shop.order.Order".to_string();
+        order.members.push(same_module);
+
+        let classes = vec![
+            controller,
+            service,
+            order,
+            class("Java", "shop.common", "BaseService").in_module("common"),
+            class("Java", "", "Orphan").in_module(""),
+        ];
+
+        let graph = ModuleGraph::build(&classes);
+
+        assert_eq!(
+            vec!["common", "order", "web"],
+            graph.modules.keys().collect::<Vec<&String>>()
+        );
+        assert_eq!(vec!["shop.order"], graph.modules["order"]);
+        assert_eq!(2, graph.edges.len());
+        assert_eq!("order", graph.edges[0].source);
+        assert_eq!("common", graph.edges[0].target);
+        assert_eq!(1, graph.edges[0].weight);
+        assert_eq!("web", graph.edges[1].source);
+        assert_eq!("order", graph.edges[1].target);
+        assert_eq!(2, graph.edges[1].weight);
+    }
+
+    #[test]
+    fn should_return_empty_without_workspace() {
+        let dir = TempDir::new().unwrap();
+        write(dir.path(), "src/main.rs", "fn main() {}");
+
+        assert!(detect_modules(dir.path()).is_empty());
+    }
+}
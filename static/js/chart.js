@@ -2,8 +2,9 @@ function renderVertical(data) {
     var width = 1440;
     var height = 2048;
 
-    const nodes = data.nodes.map(({id, group}) => ({
+    const nodes = data.nodes.map(({id, name, group}) => ({
         id,
+        name: name || id,
         sourceLinks: [],
         targetLinks: [],
         group
@@ -52,7 +53,7 @@ function renderVertical(data) {
             .attr("x", d => d.x < Math.PI ? 6 : -6)
             .attr("dy", "0.35em")
             .attr("fill", d => d3.lab(color(d.group)).darker(2))
-            .text(d => d.id))
+            .text(d => d.name))
         .call(g => g.append("circle")
             .attr("r", 3)
             .attr("fill", d => color(d.group)));
@@ -82,15 +83,15 @@ function renderVertical(data) {
             label.classed("secondary", n => n.sourceLinks.some(l => l.target === d) || n.targetLinks.some(l => l.source === d));
             path.classed("primary", l => l.source === d || l.target === d).filter(".primary").raise();
 
-            var result = "<h3>" + d.id + "</h3>";
+            var result = "<h3>" + d.name + "</h3>";
             result += "usedby count:" + d.targetLinks.length + "<br>";
             result += "use count:" + d.sourceLinks.length + "<br>";
             for (let linkElement of d.targetLinks) {
-                result += "usedby: " + linkElement.source.id + "<br>"
+                result += "usedby: " + linkElement.source.name + "<br>"
             }
 
             for (let linkElement of d.sourceLinks) {
-                result += "use: " + linkElement.target.id + "<br>"
+                result += "use: " + linkElement.target.name + "<br>"
             }
 
             div.transition()
@@ -142,13 +143,13 @@ function renderVertical(data) {
         var value = d3.select("#selectSort").node().value;
         var selectMap = {
             Group: {
-                value: (a, b) => a.group - b.group || d3.ascending(a.id, b.id)
+                value: (a, b) => a.group - b.group || d3.ascending(a.name, b.name)
             },
             Name: {
-                value: (a, b) => d3.ascending(a.id, b.id)
+                value: (a, b) => d3.ascending(a.name, b.name)
             },
             Frequency: {
-                value: (a, b) => d3.sum(b.sourceLinks, l => l.value) + d3.sum(b.targetLinks, l => l.value) - d3.sum(a.sourceLinks, l => l.value) - d3.sum(a.targetLinks, l => l.value) || d3.ascending(a.id, b.id)
+                value: (a, b) => d3.sum(b.sourceLinks, l => l.value) + d3.sum(b.targetLinks, l => l.value) - d3.sum(a.sourceLinks, l => l.value) - d3.sum(a.targetLinks, l => l.value) || d3.ascending(a.name, b.name)
             }
         }
 
@@ -186,10 +187,22 @@ function renderCircle(originData) {
 
     var jdata = []
     var dMap = {}
+    // ids of classes contain dots, so nodes are named by class names, and classes of the same
+    // name are numbered to keep them apart
+    var names = new Map()
+    var counts = new Map()
+    for (let node of originData.nodes) {
+        let name = node.name || node.id
+        let count = (counts.get(name) || 0) + 1
+        counts.set(name, count)
+        names.set(node.id, count > 1 ? `${name} (${count})` : name)
+    }
+    var nameOf = id => names.get(id) || id
 
     for (let node of originData.nodes) {
         dMap[node.id] = {
-            name: "root." + node.id,
+            id: node.id,
+            name: "root." + nameOf(node.id),
             imports: [],
             size: node.group
         }
@@ -200,11 +213,12 @@ function renderCircle(originData) {
             continue
         }
         if (dMap[link.source]) {
-            dMap[link.source].imports.push("root." + link.target)
+            dMap[link.source].imports.push("root." + nameOf(link.target))
         } else {
             dMap[link.source] = {
-                name: "root." + link.source,
-                imports: ["root." + link.target],
+                id: link.source,
+                name: "root." + nameOf(link.source),
+                imports: ["root." + nameOf(link.target)],
                 size: 1
             }
         }
@@ -294,7 +308,7 @@ function renderCircle(originData) {
                 return ""
             })).attr("fill", null).attr("font-weight", null);
         })
-        .call(text => text.append("title").text(d => `${id(d)}
+        .call(text => text.append("title").text(d => `${d.data.id || id(d)}
 ${d.outgoing.length} outgoing
 ${d.incoming.length} incoming`));
 